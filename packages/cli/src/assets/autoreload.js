@@ -3,23 +3,206 @@
 
 (function () {
   var protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
-  var url = protocol + '//' + window.location.host + '/_dioxus/ws';
+  var origin = window.__DIOXUS_CONTROL__ || (protocol + '//' + window.location.host);
+  var url = origin + '/_dioxus/ws';
   var poll_interval = 8080;
-  var reload_upon_connect = () => {
+  // A dropped connection, e.g. on flaky wifi or after waking from sleep, is retried this soon
+  // before falling back to the poll interval
+  var reconnect_grace = typeof window.__DIOXUS_RECONNECT_GRACE__ === 'number'
+      ? window.__DIOXUS_RECONNECT_GRACE__ : 1000;
+  var preserve_navigation = window.__DIOXUS_PRESERVE_NAVIGATION__ === true;
+  var navigation_key = 'dioxus-navigation';
+  var preserve_forms = window.__DIOXUS_PRESERVE_FORMS__ === true;
+  var forms_key = 'dioxus-forms';
+  var soft_reload = window.__DIOXUS_RELOAD_STYLE__ === 'soft';
+  var soft_reload_key = 'dioxus-soft-reload';
+  // Shared by the pages of the dev server, so they can tell which of them was focused last
+  var focused_key = 'dioxus-focused';
+  var page_id = Math.random().toString(36).slice(2);
+  var stale = false;
+
+  var reload = () => {
+      if (preserve_navigation) {
+          sessionStorage.setItem(navigation_key, JSON.stringify({
+              url: window.location.href,
+              x: window.scrollX,
+              y: window.scrollY,
+          }));
+      }
+      if (preserve_forms) {
+          save_fields();
+      }
+      if (soft_reload) {
+          fade_out();
+          return;
+      }
+      window.location.reload();
+  };
+
+  // The head of the reloaded page paints this background instead of a white flash
+  var fade_out = () => {
+      sessionStorage.setItem(soft_reload_key, getComputedStyle(document.body).backgroundColor);
+      var html = document.documentElement;
+      html.style.transition = 'opacity 120ms ease-out';
+      html.style.opacity = '0.6';
+      window.setTimeout(() => window.location.reload(), 120);
+  };
+
+  // Passwords and files aren't kept, the session storage is readable by every script of the page
+  var form_fields = () => Array.from(document.querySelectorAll('input, textarea, select'))
+      .filter((field) => field.type !== 'password' && field.type !== 'file');
+
+  // Fields without an id or name are told apart by their position on the page
+  var field_key = (field, index) => field.id ? '#' + field.id
+      : field.name ? field.name + (field.type === 'radio' ? '=' + field.value : '')
+      : '@' + index;
+
+  var save_fields = () => {
+      var fields = {};
+      form_fields().forEach((field, index) => {
+          if (field.type === 'checkbox' || field.type === 'radio') {
+              fields[field_key(field, index)] = { checked: field.checked };
+          } else {
+              fields[field_key(field, index)] = { value: field.value };
+          }
+      });
+      sessionStorage.setItem(forms_key, JSON.stringify(fields));
+  };
+
+  var restore_fields = () => {
+      var saved = sessionStorage.getItem(forms_key);
+      sessionStorage.removeItem(forms_key);
+      if (!saved) {
+          return;
+      }
+      saved = JSON.parse(saved);
+      // The app renders its fields once its wasm is loaded, so retry until they show up
+      var deadline = Date.now() + 5000;
+      var restore = () => {
+          var fields = form_fields();
+          fields.forEach((field, index) => {
+              var value = saved[field_key(field, index)];
+              if (!value) {
+                  return;
+              }
+              // Fire the events the app listens to, so its state matches the restored fields
+              if ('checked' in value) {
+                  field.checked = value.checked;
+                  field.dispatchEvent(new Event('change', { bubbles: true }));
+              } else {
+                  field.value = value.value;
+                  field.dispatchEvent(new Event('input', { bubbles: true }));
+              }
+          });
+          if (fields.length === 0 && Date.now() < deadline) {
+              window.setTimeout(restore, 50);
+          }
+      };
+      restore();
+  };
+
+  var restore_navigation = () => {
+      var saved = sessionStorage.getItem(navigation_key);
+      sessionStorage.removeItem(navigation_key);
+      if (!saved) {
+          return;
+      }
+      saved = JSON.parse(saved);
+      if (saved.url !== window.location.href) {
+          window.history.replaceState(window.history.state, '', saved.url);
+      }
+      // The app renders once its wasm is loaded, so retry until the page is long enough
+      var deadline = Date.now() + 5000;
+      var scroll = () => {
+          window.scrollTo(saved.x, saved.y);
+          if ((window.scrollX !== saved.x || window.scrollY !== saved.y) && Date.now() < deadline) {
+              window.setTimeout(scroll, 50);
+          }
+      };
+      scroll();
+  };
+  var record_focus = () => {
+      localStorage.setItem(focused_key, page_id);
+      // Catch up on a reload skipped while another page was focused
+      if (stale) {
+          reload();
+      }
+  };
+
+  var reload_if_focused = () => {
+      if (localStorage.getItem(focused_key) === page_id) {
+          reload();
+      } else {
+          stale = true;
+      }
+  };
+
+  // The reload after reconnecting catches up on the messages missed in the meantime
+  var reload_upon_connect = (delay) => {
       window.setTimeout(
           () => {
               var ws = new WebSocket(url);
-              ws.onopen = () => window.location.reload();
-              ws.onclose = reload_upon_connect;
+              ws.onopen = reload;
+              ws.onclose = () => reload_upon_connect(poll_interval);
           },
-          poll_interval);
+          delay);
   };
 
+  var building_id = 'dioxus-building';
+
+  var show_building = () => {
+      if (document.getElementById(building_id)) {
+          return;
+      }
+      var overlay = document.createElement('div');
+      overlay.id = building_id;
+      overlay.textContent = 'Building\u2026';
+      overlay.style.cssText = 'position:fixed;right:12px;bottom:12px;z-index:2147483647;'
+          + 'padding:6px 12px;border-radius:4px;background:rgba(0,0,0,0.75);color:#fff;'
+          + 'font:13px sans-serif;pointer-events:none;';
+      document.body.appendChild(overlay);
+  };
+
+  var hide_building = () => {
+      var overlay = document.getElementById(building_id);
+      if (overlay) {
+          overlay.remove();
+      }
+  };
+
+  var reload_stylesheets = () => {
+      document.querySelectorAll('link[rel="stylesheet"]').forEach((link) => {
+          var href = new URL(link.href);
+          href.searchParams.set('dioxus-css', Date.now());
+          link.href = href.toString();
+      });
+  };
+
+  if (preserve_navigation) {
+      restore_navigation();
+  }
+  if (preserve_forms) {
+      restore_fields();
+  }
+
+  window.addEventListener('focus', record_focus);
+  if (document.hasFocus()) {
+      localStorage.setItem(focused_key, page_id);
+  }
+
   var ws = new WebSocket(url);
   ws.onmessage = (ev) => {
       if (ev.data == "reload") {
-          window.location.reload();
+          reload();
+      } else if (ev.data == "reload-focused") {
+          reload_if_focused();
+      } else if (ev.data == "css") {
+          reload_stylesheets();
+      } else if (ev.data == "building") {
+          show_building();
+      } else if (ev.data == "built") {
+          hide_building();
       }
   };
-  ws.onclose = reload_upon_connect;
+  ws.onclose = () => reload_upon_connect(reconnect_grace);
 })()
\ No newline at end of file
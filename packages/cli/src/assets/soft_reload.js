@@ -0,0 +1,23 @@
+// Dioxus-CLI
+// https://github.com/DioxusLabs/cli
+
+// Runs in the head of the dev page with `reload_style = "soft"`, before anything is painted:
+// the reloaded page starts dimmed on the background of the previous one and fades in
+(function () {
+  var background = sessionStorage.getItem('dioxus-soft-reload');
+  if (background === null) {
+      return;
+  }
+  sessionStorage.removeItem('dioxus-soft-reload');
+  var html = document.documentElement;
+  html.style.background = background;
+  html.style.opacity = '0.6';
+  window.addEventListener('load', () => {
+      html.style.transition = 'opacity 150ms ease-in';
+      html.style.opacity = '';
+      window.setTimeout(() => {
+          html.style.transition = '';
+          html.style.background = '';
+      }, 150);
+  });
+})()
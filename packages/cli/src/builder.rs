@@ -1,5 +1,5 @@
 use crate::{
-    config::{CrateConfig, ExecutableType},
+    config::{CrateConfig, ExecutableType, ReloadStyle},
     error::{Error, Result},
     tools::Tool,
     DioxusConfig,
@@ -8,10 +8,11 @@ use cargo_metadata::{diagnostic::Diagnostic, Message};
 use indicatif::{ProgressBar, ProgressStyle};
 use serde::Serialize;
 use std::{
+    collections::BTreeMap,
     fs::{copy, create_dir_all, File},
     io::Read,
     panic,
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::Command,
     time::Duration,
 };
@@ -21,6 +22,8 @@ use wasm_bindgen_cli_support::Bindgen;
 pub struct BuildResult {
     pub warnings: Vec<Diagnostic>,
     pub elapsed_time: u128,
+    /// How many millis each phase of the build took
+    pub phases: Vec<(&'static str, u128)>,
 }
 
 pub fn build(config: &CrateConfig, quiet: bool) -> Result<BuildResult> {
@@ -33,7 +36,6 @@ pub fn build(config: &CrateConfig, quiet: bool) -> Result<BuildResult> {
 
     let CrateConfig {
         out_dir,
-        crate_dir,
         target_dir,
         asset_dir,
         executable,
@@ -41,55 +43,25 @@ pub fn build(config: &CrateConfig, quiet: bool) -> Result<BuildResult> {
         ..
     } = config;
 
+    let mut phases = Vec::new();
+    let mut phase_start = std::time::Instant::now();
+    let mut end_phase = |name: &'static str| {
+        phases.push((name, phase_start.elapsed().as_millis()));
+        phase_start = std::time::Instant::now();
+    };
+
     // start to build the assets
     let ignore_files = build_assets(config)?;
+    end_phase("assets");
 
     let t_start = std::time::Instant::now();
 
     // [1] Build the .wasm module
     log::info!("🚅 Running build command...");
-    let cmd = subprocess::Exec::cmd("cargo");
-    let cmd = cmd
-        .cwd(crate_dir)
-        .arg("build")
-        .arg("--target")
-        .arg("wasm32-unknown-unknown")
-        .arg("--message-format=json");
-
-    let cmd = if config.release {
-        cmd.arg("--release")
-    } else {
-        cmd
-    };
-    let cmd = if config.verbose {
-        cmd.arg("--verbose")
-    } else {
-        cmd
-    };
-
-    let cmd = if quiet { cmd.arg("--quiet") } else { cmd };
-
-    let cmd = if config.custom_profile.is_some() {
-        let custom_profile = config.custom_profile.as_ref().unwrap();
-        cmd.arg("--profile").arg(custom_profile)
-    } else {
-        cmd
-    };
-
-    let cmd = if config.features.is_some() {
-        let features_str = config.features.as_ref().unwrap().join(" ");
-        cmd.arg("--features").arg(features_str)
-    } else {
-        cmd
-    };
-
-    let cmd = match executable {
-        ExecutableType::Binary(name) => cmd.arg("--bin").arg(name),
-        ExecutableType::Lib(name) => cmd.arg("--lib").arg(name),
-        ExecutableType::Example(name) => cmd.arg("--example").arg(name),
-    };
+    let cmd = cargo_command(config, "build", quiet);
 
     let warning_messages = prettier_build(cmd)?;
+    end_phase("cargo");
 
     // [2] Establish the output directory structure
     let bindgen_outdir = out_dir.join("assets").join("dioxus");
@@ -129,6 +101,7 @@ pub fn build(config: &CrateConfig, quiet: bool) -> Result<BuildResult> {
     if bindgen_result.is_err() {
         return Err(Error::BuildFailed("Bindgen build failed! \nThis is probably due to the Bindgen version, dioxus-cli using `0.2.81` Bindgen crate.".to_string()));
     }
+    end_phase("wasm-bindgen");
 
     // check binaryen:wasm-opt tool
     let dioxus_tools = dioxus_config.application.tools.clone().unwrap_or_default();
@@ -155,6 +128,9 @@ pub fn build(config: &CrateConfig, quiet: bool) -> Result<BuildResult> {
                         if config.release {
                             args.push("-Oz");
                         }
+                        if config.debug_symbols {
+                            args.push("-g");
+                        }
                         binaryen.call("wasm-opt", args)?;
                     }
                 }
@@ -166,6 +142,8 @@ pub fn build(config: &CrateConfig, quiet: bool) -> Result<BuildResult> {
         }
     }
 
+    end_phase("wasm-opt");
+
     // [5][OPTIONAL] If tailwind is enabled and installed we run it to generate the CSS
     if dioxus_tools.contains_key("tailwindcss") {
         let info = dioxus_tools.get("tailwindcss").unwrap();
@@ -205,6 +183,8 @@ pub fn build(config: &CrateConfig, quiet: bool) -> Result<BuildResult> {
         }
     }
 
+    end_phase("tailwind");
+
     // this code will copy all public file to the output dir
     let copy_options = fs_extra::dir::CopyOptions {
         overwrite: true,
@@ -214,7 +194,10 @@ pub fn build(config: &CrateConfig, quiet: bool) -> Result<BuildResult> {
         content_only: false,
         depth: 0,
     };
-    if asset_dir.is_dir() {
+    let copy_concurrency = dioxus_config.web.serve.copy_concurrency.unwrap_or(1);
+    if asset_dir.is_dir() && copy_concurrency > 1 {
+        copy_assets_parallel(asset_dir, out_dir, &ignore_files, copy_concurrency)?;
+    } else if asset_dir.is_dir() {
         for entry in std::fs::read_dir(asset_dir)? {
             let path = entry?.path();
             if path.is_file() {
@@ -237,13 +220,70 @@ pub fn build(config: &CrateConfig, quiet: bool) -> Result<BuildResult> {
         }
     }
 
+    end_phase("copy public files");
+
     let t_end = std::time::Instant::now();
     Ok(BuildResult {
         warnings: warning_messages,
         elapsed_time: (t_end - t_start).as_millis(),
+        phases,
     })
 }
 
+/// Copy the files of the asset directory into the output directory on `concurrency` threads,
+/// reporting every file that failed
+fn copy_assets_parallel(
+    asset_dir: &Path,
+    out_dir: &Path,
+    ignore_files: &[PathBuf],
+    concurrency: usize,
+) -> Result<()> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(asset_dir).min_depth(1) {
+        let entry =
+            entry.map_err(|err| Error::BuildFailed(format!("Failed to read assets: {}", err)))?;
+        let relative = entry.path().strip_prefix(asset_dir).unwrap().to_path_buf();
+        // The directories are created up front, so the threads never race on creating them
+        if entry.file_type().is_dir() {
+            create_dir_all(out_dir.join(&relative))?;
+        } else if !ignore_files.iter().any(|ignore| ignore == entry.path()) {
+            files.push(relative);
+        }
+    }
+
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let failures = std::sync::Mutex::new(Vec::new());
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency.min(files.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let Some(file) = files.get(index) else {
+                    break;
+                };
+                if let Err(err) = copy(asset_dir.join(file), out_dir.join(file)) {
+                    failures
+                        .lock()
+                        .unwrap()
+                        .push(format!("{}: {}", file.display(), err));
+                }
+            });
+        }
+    });
+
+    let failures = failures.into_inner().unwrap();
+    if failures.is_empty() {
+        return Ok(());
+    }
+    for failure in &failures {
+        log::error!("Failed to copy {}", failure);
+    }
+    Err(Error::BuildFailed(format!(
+        "Failed to copy {} of {} public files",
+        failures.len(),
+        files.len()
+    )))
+}
+
 pub fn build_desktop(config: &CrateConfig, _is_serve: bool) -> Result<()> {
     log::info!("🚅 Running build [Desktop] command...");
 
@@ -274,6 +314,12 @@ pub fn build_desktop(config: &CrateConfig, _is_serve: bool) -> Result<()> {
         cmd.arg(features_str);
     }
 
+    for value in &config.cargo_config {
+        cmd.arg("--config");
+        cmd.arg(value);
+    }
+    cmd.envs(&config.cargo_env);
+
     match &config.executable {
         crate::ExecutableType::Binary(name) => cmd.arg("--bin").arg(name),
         crate::ExecutableType::Lib(name) => cmd.arg("--lib").arg(name),
@@ -368,6 +414,84 @@ pub fn build_desktop(config: &CrateConfig, _is_serve: bool) -> Result<()> {
     Ok(())
 }
 
+/// The cargo command building or checking the wasm of the crate with the configured options
+fn cargo_command(config: &CrateConfig, subcommand: &str, quiet: bool) -> subprocess::Exec {
+    let cmd = subprocess::Exec::cmd("cargo");
+    let cmd = cmd
+        .cwd(&config.crate_dir)
+        .arg(subcommand)
+        .arg("--target")
+        .arg("wasm32-unknown-unknown")
+        .arg("--message-format=json");
+
+    let cmd = if config.release {
+        cmd.arg("--release")
+    } else {
+        cmd
+    };
+    let cmd = if config.verbose {
+        cmd.arg("--verbose")
+    } else {
+        cmd
+    };
+
+    let cmd = if quiet { cmd.arg("--quiet") } else { cmd };
+
+    // Keep debuginfo in whatever profile we build with so devtools can symbolicate the wasm
+    let cmd = if config.debug_symbols {
+        let profile = match (&config.custom_profile, config.release) {
+            (Some(profile), _) => profile.as_str(),
+            (None, true) => "release",
+            (None, false) => "dev",
+        };
+        cmd.env(
+            format!(
+                "CARGO_PROFILE_{}_DEBUG",
+                profile.to_uppercase().replace('-', "_")
+            ),
+            "true",
+        )
+    } else {
+        cmd
+    };
+
+    let cmd = if config.custom_profile.is_some() {
+        let custom_profile = config.custom_profile.as_ref().unwrap();
+        cmd.arg("--profile").arg(custom_profile)
+    } else {
+        cmd
+    };
+
+    let cmd = if config.features.is_some() {
+        let features_str = config.features.as_ref().unwrap().join(" ");
+        cmd.arg("--features").arg(features_str)
+    } else {
+        cmd
+    };
+
+    let cmd = config
+        .cargo_config
+        .iter()
+        .fold(cmd, |cmd, value| cmd.arg("--config").arg(value));
+    let cmd = config
+        .cargo_env
+        .iter()
+        .fold(cmd, |cmd, (key, value)| cmd.env(key, value));
+
+    match &config.executable {
+        ExecutableType::Binary(name) => cmd.arg("--bin").arg(name),
+        ExecutableType::Lib(name) => cmd.arg("--lib").arg(name),
+        ExecutableType::Example(name) => cmd.arg("--example").arg(name),
+    }
+}
+
+/// Run `cargo check` for the wasm target, failing with the first error without generating code
+pub fn check(config: &CrateConfig) -> Result<()> {
+    log::info!("🔍 Checking the project before building...");
+    prettier_build(cargo_command(config, "check", true))?;
+    Ok(())
+}
+
 fn prettier_build(cmd: subprocess::Exec) -> anyhow::Result<Vec<Diagnostic>> {
     let mut warning_messages: Vec<Diagnostic> = vec![];
 
@@ -430,8 +554,8 @@ fn prettier_build(cmd: subprocess::Exec) -> anyhow::Result<Vec<Diagnostic>> {
     Ok(warning_messages)
 }
 
-pub fn gen_page(config: &DioxusConfig, serve: bool) -> String {
-    let crate_root = crate::cargo::crate_root().unwrap();
+/// Generate the html page of the crate at `crate_root`, with the reload client if `serve` is set
+pub fn gen_page(config: &DioxusConfig, crate_root: &Path, serve: bool) -> String {
     let custom_html_file = crate_root.join("index.html");
     let mut html = if custom_html_file.is_file() {
         let mut buf = String::new();
@@ -476,6 +600,21 @@ pub fn gen_page(config: &DioxusConfig, serve: bool) -> String {
 
     replace_or_insert_before("{style_include}", &style_str, "</head", &mut html);
 
+    // Read again on every regeneration, so edits of the snippet file apply on the next rebuild
+    let head_snippet = if serve {
+        let mut head_snippet = config.web.serve.head_snippet(crate_root);
+        if config.web.serve.reload_style == Some(ReloadStyle::Soft) {
+            head_snippet += &format!(
+                "<script>{}</script>",
+                include_str!("./assets/soft_reload.js")
+            );
+        }
+        head_snippet
+    } else {
+        String::new()
+    };
+    replace_or_insert_before("{head_snippet}", &head_snippet, "</head", &mut html);
+
     let mut script_str = String::new();
     for script in script_list {
         script_str.push_str(&format!(
@@ -487,10 +626,14 @@ pub fn gen_page(config: &DioxusConfig, serve: bool) -> String {
     replace_or_insert_before("{script_include}", &script_str, "</body", &mut html);
 
     if serve {
-        html += &format!(
-            "<script>{}</script>",
-            include_str!("./assets/autoreload.js")
-        );
+        let env = resolve_dev_env(crate_root, config);
+        if !env.is_empty() {
+            // Keep a value containing `</script>` from closing the tag early
+            let env = serde_json::to_string(&env).unwrap().replace("</", "<\\/");
+            html += &format!("<script>window.__DIOXUS_ENV__ = {};</script>", env);
+        }
+
+        html += &reload_client(config);
     }
 
     let base_path = match &config.web.app.base_path {
@@ -521,6 +664,17 @@ pub fn gen_page(config: &DioxusConfig, serve: bool) -> String {
         );
     }
 
+    // The file service ignores the query, so the urls still point at the same files
+    if serve && config.web.serve.cache_bust.unwrap_or(false) {
+        let version = chrono::Local::now().timestamp_millis();
+        for file in [format!("{}.js", app_name), format!("{}_bg.wasm", app_name)] {
+            html = html.replace(
+                &format!("/{}\"", file),
+                &format!("/{}?v={}\"", file, version),
+            );
+        }
+    }
+
     let title = config
         .web
         .app
@@ -533,6 +687,92 @@ pub fn gen_page(config: &DioxusConfig, serve: bool) -> String {
     html
 }
 
+/// Resolve the variables injected into the dev page as `window.__DIOXUS_ENV__`.
+///
+/// The keys come from `web.serve.env` and the crate's `.env` file. The process environment
+/// takes precedence over the `.env` file, which takes precedence over the config.
+pub fn resolve_dev_env(crate_root: &Path, config: &DioxusConfig) -> BTreeMap<String, String> {
+    let mut env: BTreeMap<String, String> = config
+        .web
+        .serve
+        .env
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+
+    let dotenv = crate_root.join(".env");
+    if dotenv.is_file() {
+        match dotenvy::from_path_iter(&dotenv) {
+            Ok(vars) => {
+                for var in vars {
+                    match var {
+                        Ok((key, value)) => {
+                            env.insert(key, value);
+                        }
+                        Err(err) => log::warn!("Skipping invalid line in .env: {}", err),
+                    }
+                }
+            }
+            Err(err) => log::warn!("Failed to read {}: {}", dotenv.display(), err),
+        }
+    }
+
+    for (key, value) in env.iter_mut() {
+        if let Ok(process_value) = std::env::var(key) {
+            *value = process_value;
+        }
+    }
+
+    env
+}
+
+/// The scripts of the reload client injected into dev pages
+pub fn reload_client(config: &DioxusConfig) -> String {
+    let mut client = String::new();
+
+    if config.web.serve.preserve_navigation.unwrap_or(false) {
+        client += "<script>window.__DIOXUS_PRESERVE_NAVIGATION__ = true;</script>";
+    }
+    if config.web.serve.preserve_form_state.unwrap_or(false) {
+        client += "<script>window.__DIOXUS_PRESERVE_FORMS__ = true;</script>";
+    }
+    if let Some(grace) = config.web.serve.reload_grace_ms {
+        client += &format!(
+            "<script>window.__DIOXUS_RECONNECT_GRACE__ = {};</script>",
+            grace
+        );
+    }
+    if config.web.serve.reload_style == Some(ReloadStyle::Soft) {
+        client += "<script>window.__DIOXUS_RELOAD_STYLE__ = \"soft\";</script>";
+    }
+
+    // Point the reload and hot reload sockets at the loopback-only control listener
+    if let Some(control_port) = config.web.serve.control_port {
+        // The control listener is served over TLS along with the app
+        let scheme = match config.web.https.enabled {
+            Some(true) => "wss",
+            _ => "ws",
+        };
+        client += &format!(
+            "<script>window.__DIOXUS_CONTROL__ = \"{}://127.0.0.1:{}\";</script>",
+            scheme, control_port
+        );
+    } else if let Some(base_path) = &config.web.app.base_path {
+        // The reload and hot reload sockets live under the app's base path when several apps
+        // are served at once
+        client += &format!(
+            "<script>window.__DIOXUS_CONTROL__ = (location.protocol === \"https:\" ? \"wss://\" : \"ws://\") + location.host + \"/{}\";</script>",
+            base_path.trim_matches('/')
+        );
+    }
+    client += &format!(
+        "<script>{}</script>",
+        include_str!("./assets/autoreload.js")
+    );
+    client
+}
+
 fn replace_or_insert_before(
     replace: &str,
     with: &str,
@@ -723,3 +963,88 @@ fn build_assets(config: &CrateConfig) -> Result<Vec<PathBuf>> {
 //         install_permitted,
 //     )?)
 // }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn app_pages_reach_the_sockets_under_their_prefix() {
+        let mut config = DioxusConfig::default();
+        config.web.app.base_path = Some("/admin/".to_string());
+        let dev_page = gen_page(&config, &crate::cargo::crate_root().unwrap(), true);
+        assert!(
+            dev_page.contains("location.host + \"/admin\""),
+            "{}",
+            dev_page
+        );
+    }
+
+    #[test]
+    fn reconnect_grace_is_passed_to_the_page() {
+        let mut config = DioxusConfig::default();
+        assert!(!reload_client(&config).contains("__DIOXUS_RECONNECT_GRACE__ ="));
+
+        config.web.serve.reload_grace_ms = Some(3000);
+        assert!(reload_client(&config).contains("window.__DIOXUS_RECONNECT_GRACE__ = 3000;"));
+    }
+
+    #[test]
+    fn control_sockets_use_tls_with_https() {
+        let mut config = DioxusConfig::default();
+        config.web.serve.control_port = Some(8081);
+        assert!(reload_client(&config).contains("\"ws://127.0.0.1:8081\""));
+
+        config.web.https.enabled = Some(true);
+        assert!(reload_client(&config).contains("\"wss://127.0.0.1:8081\""));
+    }
+
+    #[test]
+    fn assets_are_copied_in_parallel() {
+        let root = tempfile::tempdir().unwrap();
+        let (asset_dir, out_dir) = (root.path().join("public"), root.path().join("dist"));
+        std::fs::create_dir_all(asset_dir.join("images/icons")).unwrap();
+        std::fs::create_dir_all(&out_dir).unwrap();
+        for file in [
+            "main.css",
+            "images/a.png",
+            "images/icons/b.svg",
+            "skip.scss",
+        ] {
+            std::fs::write(asset_dir.join(file), file).unwrap();
+        }
+
+        copy_assets_parallel(&asset_dir, &out_dir, &[asset_dir.join("skip.scss")], 4).unwrap();
+        for file in ["main.css", "images/a.png", "images/icons/b.svg"] {
+            assert_eq!(std::fs::read_to_string(out_dir.join(file)).unwrap(), file);
+        }
+        assert!(!out_dir.join("skip.scss").exists());
+    }
+
+    #[test]
+    fn build_pages_have_no_dev_markup() {
+        let mut config = DioxusConfig::default();
+        config.web.serve.preserve_navigation = Some(true);
+        config.web.serve.preserve_form_state = Some(true);
+        config.web.serve.cache_bust = Some(true);
+        config.web.serve.control_port = Some(8081);
+        config.web.serve.head_snippet = Some("<meta name=\"dev-only\">".to_string());
+        config.web.serve.reload_style = Some(ReloadStyle::Soft);
+
+        let crate_root = crate::cargo::crate_root().unwrap();
+        let dev_page = gen_page(&config, &crate_root, true);
+        assert!(dev_page.contains("/_dioxus/ws"));
+        assert!(dev_page.contains("dev-only"));
+        let head = &dev_page[..dev_page.find("</head").unwrap()];
+        assert!(head.contains("dioxus-soft-reload"), "{}", dev_page);
+
+        let build_page = gen_page(&config, &crate_root, false);
+        assert!(!build_page.contains("/_dioxus/ws"), "{}", build_page);
+        assert!(!build_page.contains("__DIOXUS_"), "{}", build_page);
+        assert!(!build_page.contains("dev-only"), "{}", build_page);
+        assert!(!build_page.contains("{head_snippet}"), "{}", build_page);
+        assert!(!build_page.contains("dioxus-soft-reload"), "{}", build_page);
+        assert!(dev_page.contains("_bg.wasm?v="), "{}", dev_page);
+        assert!(!build_page.contains("?v="), "{}", build_page);
+    }
+}
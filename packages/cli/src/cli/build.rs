@@ -54,23 +54,25 @@ impl Build {
             }
         }
 
-        let temp = gen_page(&crate_config.dioxus_config, false);
+        let temp = gen_page(&crate_config.dioxus_config, &crate_config.crate_dir, false);
 
-        let mut file = std::fs::File::create(
+        let out_dir = crate_config.crate_dir.join(
             crate_config
-                .crate_dir
-                .join(
-                    crate_config
-                        .dioxus_config
-                        .application
-                        .out_dir
-                        .clone()
-                        .unwrap_or_else(|| PathBuf::from("dist")),
-                )
-                .join("index.html"),
-        )?;
+                .dioxus_config
+                .application
+                .out_dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("dist")),
+        );
+        let mut file = std::fs::File::create(out_dir.join("index.html"))?;
         file.write_all(temp.as_bytes())?;
 
+        // A dev page `dx serve` left under another name would ship with its reload client
+        let dev_page = out_dir.join(crate_config.dioxus_config.web.serve.index_file());
+        if dev_page != out_dir.join("index.html") && dev_page.is_file() {
+            std::fs::remove_file(dev_page)?;
+        }
+
         #[cfg(feature = "plugin")]
         let _ = PluginManager::on_build_finish(&crate_config, &platform);
 
@@ -64,6 +64,62 @@ pub struct ConfigOptsServe {
     #[serde(default)]
     pub verbose: bool,
 
+    /// Keep debug symbols in the wasm so devtools can symbolicate it [default: false]
+    #[clap(long)]
+    #[serde(default)]
+    pub debug_symbols: bool,
+
+    /// Skip the plugin serve hooks, for when a plugin breaks serve [default: false]
+    #[clap(long)]
+    #[serde(default)]
+    pub no_plugins: bool,
+
+    /// Regenerate the mkcert certificates used for https and exit [default: false]
+    #[clap(long)]
+    #[serde(default)]
+    pub regen_certs: bool,
+
+    /// Validate the serve configuration and exit without building or serving [default: false]
+    #[clap(long)]
+    #[serde(default)]
+    pub check: bool,
+
+    /// Print how long each phase of every build took [default: false]
+    #[clap(long)]
+    #[serde(default)]
+    pub profile_build: bool,
+
+    /// Print how many lines of each changed .rs file were added and removed [default: false]
+    #[clap(long)]
+    #[serde(default)]
+    pub show_diffs: bool,
+
+    /// Run the initial build before serving with `web.serve.background_build`, and exit with an
+    /// error if it fails instead of serving until a rebuild succeeds [default: false]
+    #[clap(long)]
+    #[serde(default)]
+    pub fail_on_build_error: bool,
+
+    /// Print the config serve would run with as TOML and exit [default: false]
+    #[clap(long)]
+    #[serde(default)]
+    pub print_config: bool,
+
+    /// Merge the named profile of `web.serve.profiles` over the serve config
+    #[clap(long)]
+    pub serve_profile: Option<String>,
+
+    /// Color the console output: auto, always or never. auto colors a terminal unless
+    /// `NO_COLOR` is set [default: auto]
+    #[clap(long, value_enum, default_value_t = ColorChoice::Auto)]
+    #[serde(default)]
+    pub color: ColorChoice,
+
+    /// Log level directives like `RUST_LOG`'s, e.g. `info,hyper=debug`. Falls back to
+    /// `RUST_LOG`, then to info for the CLI and warn for its noisy dependencies
+    #[clap(long)]
+    pub log: Option<String>,
+
     /// Build with custom profile
     #[clap(long)]
     pub profile: Option<String>,
@@ -77,6 +133,16 @@ pub struct ConfigOptsServe {
     #[serde(default)]
     pub hot_reload: bool,
 
+    /// Build once and rebuild only when the checked out git ref changes [default: false]
+    #[clap(long)]
+    #[serde(default)]
+    pub preview: bool,
+
+    /// Keep a fixed status region at the top of the terminal instead of reprinting the banner [default: false]
+    #[clap(long)]
+    #[serde(default)]
+    pub dashboard: bool,
+
     /// Set cross-origin-policy to same-origin [default: false]
     #[clap(name = "cross-origin-policy")]
     #[clap(long)]
@@ -13,7 +13,7 @@ use crate::{
     cfg::{ConfigOptsBuild, ConfigOptsServe},
     custom_error,
     error::Result,
-    gen_page, server, CrateConfig, Error,
+    gen_page, server, ColorChoice, CrateConfig, Error,
 };
 use clap::{Parser, Subcommand};
 use html_parser::Dom;
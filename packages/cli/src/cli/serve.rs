@@ -16,30 +16,52 @@ pub struct Serve {
 
 impl Serve {
     pub async fn serve(self, bin: Option<PathBuf>) -> Result<()> {
-        let mut crate_config = crate::CrateConfig::new(bin)?;
-
-        // change the relase state.
-        crate_config.with_hot_reload(self.serve.hot_reload);
-        crate_config.with_cross_origin_policy(self.serve.cross_origin_policy);
-        crate_config.with_release(self.serve.release);
-        crate_config.with_verbose(self.serve.verbose);
-
-        if self.serve.example.is_some() {
-            crate_config.as_example(self.serve.example.unwrap());
+        crate::logging::set_color(self.serve.color);
+        if let Some(directives) = self
+            .serve
+            .log
+            .clone()
+            .or_else(|| std::env::var("RUST_LOG").ok())
+        {
+            let filter = crate::logging::LogFilter::parse(&directives)
+                .map_err(|err| Error::CustomError(format!("Invalid --log filter: {}", err)))?;
+            crate::logging::set_log_filter(filter);
         }
+        let mut crate_config = crate::CrateConfig::new(bin)?;
+        self.configure(&mut crate_config)?;
 
-        if self.serve.profile.is_some() {
-            crate_config.set_profile(self.serve.profile.unwrap());
+        if self.serve.regen_certs {
+            let (cert_path, key_path) = server::regenerate_certificates(&crate_config)?;
+            log::info!("🔐 Regenerated {} and {}", cert_path, key_path);
+            return Ok(());
         }
 
-        if self.serve.features.is_some() {
-            crate_config.set_features(self.serve.features.unwrap());
+        if self.serve.check {
+            let problems = server::check_config(&crate_config).await;
+            if problems.is_empty() {
+                log::info!("✅ The serve configuration is valid");
+                return Ok(());
+            }
+            for problem in &problems {
+                log::error!("{}", problem);
+            }
+            return custom_error!(
+                "Found {} problems in the serve configuration",
+                problems.len()
+            );
         }
 
         // Subdirectories don't work with the server
         crate_config.dioxus_config.web.app.base_path = None;
 
-        let platform = self.serve.platform.unwrap_or_else(|| {
+        server::resolve_out_dir(&mut crate_config)?;
+        // With the out_dir serve actually uses
+        if self.serve.print_config {
+            print!("{}", crate_config.effective_config()?);
+            return Ok(());
+        }
+
+        let platform = self.serve.platform.clone().unwrap_or_else(|| {
             crate_config
                 .dioxus_config
                 .application
@@ -68,17 +90,128 @@ impl Serve {
             return custom_error!("Unsupported platform target.");
         }
 
+        // serve every configured app under its own path prefix
+        if let Some(apps) = crate_config.dioxus_config.web.serve.apps.clone() {
+            let mut served = Vec::new();
+            for app in apps {
+                let mut app_config = crate::CrateConfig::new(Some(app.path))?;
+                self.configure(&mut app_config)?;
+                server::resolve_out_dir(&mut app_config)?;
+
+                let prefix = app.prefix.trim_matches('/').to_string();
+                app_config.dioxus_config.web.app.base_path = Some(prefix.clone());
+                let serve_config = &mut app_config.dioxus_config.web.serve;
+                if serve_config.control_port.take().is_some() {
+                    log::warn!(
+                        "web.serve.control_port isn't supported when serving several apps, /{} keeps its control routes",
+                        prefix
+                    );
+                }
+                Serve::regen_dev_page(&app_config)?;
+
+                served.push(server::ServedApp {
+                    prefix: format!("/{}", prefix),
+                    config: app_config,
+                });
+            }
+            server::startup_apps(self.serve.port, crate_config, served, self.serve.open, None)
+                .await?;
+            return Ok(());
+        }
+
         // generate dev-index page
         Serve::regen_dev_page(&crate_config)?;
 
         // start the develop server
-        server::startup(self.serve.port, crate_config.clone(), self.serve.open).await?;
+        server::startup(self.serve.port, crate_config.clone(), self.serve.open, None).await?;
 
         Ok(())
     }
 
+    /// Apply the serve profile and the command line options to a crate config
+    fn configure(&self, crate_config: &mut CrateConfig) -> Result<()> {
+        if let Some(profile) = &self.serve.serve_profile {
+            crate_config
+                .dioxus_config
+                .web
+                .apply_serve_profile(profile)?;
+            crate_config.set_serve_profile(profile.clone());
+        }
+        server::discover_backends(crate_config)?;
+
+        // change the relase state.
+        crate_config.with_hot_reload(self.serve.hot_reload);
+        crate_config.with_preview(self.serve.preview);
+        crate_config.with_dashboard(self.serve.dashboard);
+        crate_config.with_cross_origin_policy(self.serve.cross_origin_policy);
+        crate_config.with_release(self.serve.release);
+        crate_config.with_verbose(self.serve.verbose);
+        crate_config.with_debug_symbols(self.serve.debug_symbols);
+        crate_config.with_plugins_enabled(!self.serve.no_plugins);
+        crate_config.with_profile_build(self.serve.profile_build);
+        crate_config.with_show_diffs(self.serve.show_diffs);
+        crate_config.with_fail_on_build_error(self.serve.fail_on_build_error);
+
+        if let Some(example) = &self.serve.example {
+            crate_config.as_example(example.clone());
+        }
+
+        if let Some(profile) = &self.serve.profile {
+            crate_config.set_profile(profile.clone());
+        }
+
+        if let Some(features) = &self.serve.features {
+            crate_config.set_features(features.clone());
+        }
+
+        // Cargo rejects unknown features, so the dev feature is only enabled if it's declared
+        let dev_feature = crate_config
+            .dioxus_config
+            .web
+            .serve
+            .dev_feature()
+            .to_string();
+        if crate_config.manifest.features.contains_key(&dev_feature) {
+            let mut features = crate_config.features.clone().unwrap_or_default();
+            if !features.contains(&dev_feature) {
+                features.push(dev_feature);
+            }
+            crate_config.set_features(features);
+        }
+
+        // Only serve builds get the dev profile overrides and target directory
+        let mut overrides = crate_config
+            .dioxus_config
+            .web
+            .serve
+            .dev_profile_overrides(crate_config.profile_name());
+        if let Some(target_dir) = &crate_config.dioxus_config.web.serve.target_dir {
+            let target_dir = crate_config.crate_dir.join(target_dir);
+            overrides.push(format!(
+                "build.target-dir={}",
+                toml::Value::String(target_dir.display().to_string())
+            ));
+            crate_config.target_dir = target_dir;
+        }
+        crate_config.set_cargo_config(overrides);
+        if let Some(build_env) = crate_config.dioxus_config.web.serve.build_env.clone() {
+            crate_config.set_cargo_env(build_env);
+        }
+        Ok(())
+    }
+
     pub fn regen_dev_page(crate_config: &CrateConfig) -> Result<()> {
-        let serve_html = gen_page(&crate_config.dioxus_config, true);
+        let serve_html = gen_page(&crate_config.dioxus_config, &crate_config.crate_dir, true);
+        let serve_html = match server::transform_html(crate_config, serve_html.clone()) {
+            Ok(html) => html,
+            Err(err) => {
+                log::warn!(
+                    "Serving the untransformed dev page, html_transform failed: {}",
+                    err
+                );
+                serve_html
+            }
+        };
 
         let dist_path = crate_config.crate_dir.join(
             crate_config
@@ -91,7 +224,7 @@ impl Serve {
         if !dist_path.is_dir() {
             create_dir_all(&dist_path)?;
         }
-        let index_path = dist_path.join("index.html");
+        let index_path = dist_path.join(crate_config.dioxus_config.web.serve.index_file());
         let mut file = std::fs::File::create(index_path)?;
         file.write_all(serve_html.as_bytes())?;
 
@@ -1,7 +1,7 @@
 use crate::error::Result;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     path::{Path, PathBuf},
 };
 
@@ -33,8 +33,11 @@ impl DioxusConfig {
             }
             Err(_) => return Ok(None),
         };
-        let crate_dir = crate_dir.as_path();
+        Self::load_from(&crate_dir)
+    }
 
+    /// Read the `Dioxus.toml` of the given crate directory, if it has one
+    pub fn load_from(crate_dir: &Path) -> crate::error::Result<Option<DioxusConfig>> {
         let Some(dioxus_conf_file) = acquire_dioxus_toml(crate_dir) else {
             return Ok(None);
         };
@@ -52,7 +55,7 @@ impl DioxusConfig {
     }
 }
 
-fn acquire_dioxus_toml(dir: &Path) -> Option<PathBuf> {
+pub(crate) fn acquire_dioxus_toml(dir: &Path) -> Option<PathBuf> {
     // prefer uppercase
     let uppercase_conf = dir.join("Dioxus.toml");
     if uppercase_conf.is_file() {
@@ -91,6 +94,7 @@ impl Default for DioxusConfig {
                     watch_path: Some(vec![PathBuf::from("src")]),
                     reload_html: Some(false),
                     index_on_404: Some(true),
+                    ..Default::default()
                 },
                 resource: WebResourceConfig {
                     dev: WebDevResourceConfig {
@@ -105,7 +109,11 @@ impl Default for DioxusConfig {
                     mkcert: None,
                     key_path: None,
                     cert_path: None,
+                    install_ca: None,
+                    client_ca: None,
+                    certificates: None,
                 },
+                serve: WebServeConfig::default(),
             },
             plugin: toml::Value::Table(toml::map::Map::new()),
         }
@@ -132,6 +140,43 @@ pub struct WebConfig {
     pub resource: WebResourceConfig,
     #[serde(default)]
     pub https: WebHttpsConfig,
+    #[serde(default)]
+    pub serve: WebServeConfig,
+}
+
+impl WebConfig {
+    /// Merge the serve profile of the given name over `[web.serve]`. A `proxy` list in the profile
+    /// replaces the `[[web.proxy]]` entries, keys the profile doesn't set keep their values.
+    pub fn apply_serve_profile(&mut self, name: &str) -> Result<()> {
+        let invalid = |err: &dyn std::fmt::Display| {
+            crate::Error::CustomError(format!("Invalid serve profile {}: {}", name, err))
+        };
+        let profile = self
+            .serve
+            .profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(name))
+            .cloned()
+            .ok_or_else(|| {
+                crate::Error::CustomError(format!(
+                    "No serve profile named {} in web.serve.profiles",
+                    name
+                ))
+            })?;
+        let toml::Value::Table(mut overrides) = profile else {
+            return Err(invalid(&"it isn't a table"));
+        };
+
+        if let Some(proxy) = overrides.remove("proxy") {
+            self.proxy = Some(proxy.try_into().map_err(|err| invalid(&err))?);
+        }
+        let mut serve = toml::Value::try_from(&self.serve).map_err(|err| invalid(&err))?;
+        if let toml::Value::Table(serve) = &mut serve {
+            serve.extend(overrides);
+        }
+        self.serve = serve.try_into().map_err(|err| invalid(&err))?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -140,16 +185,105 @@ pub struct WebAppConfig {
     pub base_path: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct WebProxyConfig {
+    /// Filled in from the output of `discover` if that is set
+    #[serde(default)]
     pub backend: String,
+    /// A shell command printing the backend URL, run once when serving starts
+    pub discover: Option<String>,
+    /// How many requests are forwarded at once, later ones wait for a free slot. Unlimited
+    /// if unset
+    pub concurrency: Option<usize>,
+    /// Log the method, path, status and upstream latency of every forwarded request
+    pub log: Option<bool>,
+    /// Inject the reload client into the html responses, for backends rendering the app
+    pub inject_reload: Option<bool>,
+    /// Set `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host` on forwarded requests
+    pub forward_headers: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct WebWatcherConfig {
     pub watch_path: Option<Vec<PathBuf>>,
     pub reload_html: Option<bool>,
     pub index_on_404: Option<bool>,
+    /// How many times to re-read a changed file that doesn't parse yet before hot reloading it
+    pub rsx_read_retries: Option<u32>,
+    /// The delay in milliseconds between re-reads of a changed file
+    pub rsx_read_retry_delay: Option<u64>,
+    /// Files or directories that reload the browser without rebuilding when they change
+    pub reload_only_paths: Option<Vec<PathBuf>>,
+    /// When a change to one of the `reload_only_paths` should reload the browser
+    pub reload_only_when: Option<ReloadCondition>,
+    /// Rules deciding what the hot reload watcher does with a changed path, first match wins
+    pub strategy: Option<Vec<WatcherStrategyRule>>,
+    /// The minimum time in milliseconds between the end of a full rebuild and the start of the next
+    pub min_rebuild_interval_ms: Option<u64>,
+    /// Watch the real paths of symlinked directories in the watched paths
+    pub follow_symlinks: Option<bool>,
+    /// Extra watched paths, each group with its own action
+    pub groups: Option<Vec<WatchGroup>>,
+    /// Ring the terminal bell after every successful rebuild or hot reload
+    pub bell_on_reload: Option<bool>,
+    /// A sound file played instead of the bell
+    pub reload_sound: Option<PathBuf>,
+    /// Check on startup that the watcher delivers events, on by default
+    pub self_test: Option<bool>,
+    /// Other crate directories whose rsx hot reloads, the workspace path dependencies if unset
+    pub crates: Option<Vec<PathBuf>>,
+    /// The only extensions of changed files handled, every file if unset
+    pub watch_extensions: Option<Vec<String>>,
+    /// A command run after every successful rebuild, the pages only reload if it passes
+    pub gate_command: Option<Vec<String>>,
+    /// Locale files and directories, copied into the output directory and reloaded without a
+    /// rebuild when they change
+    pub locale_paths: Option<Vec<PathBuf>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchGroup {
+    /// Files or directories, relative to the crate directory
+    pub paths: Vec<PathBuf>,
+    /// What the hot reload watcher does when one of them changes
+    pub action: ReloadStrategy,
+    /// Whether a rebuild for a change in the group reloads the pages, false for e.g. a backend
+    /// crate of the workspace (default: true)
+    pub affects_frontend: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatcherStrategyRule {
+    /// A glob matched against the changed path, relative to the crate directory
+    pub pattern: String,
+    pub action: ReloadStrategy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReloadStrategy {
+    /// Rebuild the whole project
+    Rebuild,
+    /// Hot reload the rsx in the file, rebuilding if that isn't possible
+    RsxHotreload,
+    /// Copy the asset to the output directory and refresh the page's stylesheets
+    CssInject,
+    /// Copy the asset to the output directory and reload the page
+    AssetCopy,
+    /// Reload the page without rebuilding
+    ReloadOnly,
+    /// Do nothing
+    Ignore,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReloadCondition {
+    /// Reload on every change
+    #[default]
+    Always,
+    /// Reload only if the parsed contents changed, ignoring formatting-only edits to JSON and TOML files
+    ParsedChanged,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -171,6 +305,247 @@ pub struct WebHttpsConfig {
     pub mkcert: Option<bool>,
     pub key_path: Option<String>,
     pub cert_path: Option<String>,
+    /// Install the mkcert CA into the system trust store before generating certificates
+    pub install_ca: Option<bool>,
+    /// A PEM file of the CA certificates client certificates have to be signed by. Clients
+    /// without one are rejected if it's set
+    pub client_ca: Option<String>,
+    /// The certificates served to each host by SNI, the certificate above serves other hosts
+    pub certificates: Option<Vec<WebHttpsCertificate>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebHttpsCertificate {
+    pub host: String,
+    pub cert_path: String,
+    pub key_path: String,
+}
+
+/// Options that only affect `dx serve`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct WebServeConfig {
+    /// Request the app once the server starts to surface errors early
+    pub warmup: Option<bool>,
+    /// Serve several apps from one server, each under its own path prefix
+    pub apps: Option<Vec<WebServeAppConfig>>,
+    /// Default values for the variables injected into the dev page as `window.__DIOXUS_ENV__`
+    pub env: Option<HashMap<String, String>>,
+    /// Compress responses on the fly with this level, responses are not compressed if unset
+    pub compression_level: Option<CompressionLevel>,
+    /// Start the server before the first build and show a building page until it is done
+    pub background_build: Option<bool>,
+    /// The content type to serve files with each extension with, overriding the guessed one
+    pub mime_overrides: Option<HashMap<String, String>>,
+    /// The name of the generated entry page in the output directory
+    pub index_file: Option<String>,
+    /// Files copied into the output directory after every build
+    pub extra_files: Option<Vec<ExtraFileConfig>>,
+    /// Serve the `/_dioxus/*` routes on this port of the loopback interface only
+    pub control_port: Option<u16>,
+    /// Answer with a building page and a 503 while the output is being built or missing
+    pub interstitial: Option<bool>,
+    /// Settings of the cargo profile overridden in the builds of `dx serve`
+    pub dev_profile: Option<BTreeMap<String, toml::Value>>,
+    /// Restore the url and scroll position of the page after the reload client reloads it
+    pub preserve_navigation: Option<bool>,
+    /// Restore the values of the form fields of the page after the reload client reloads it
+    pub preserve_form_state: Option<bool>,
+    /// Add the build time to the urls of the app's script and wasm, so browsers can't use a stale
+    /// cached copy
+    pub cache_bust: Option<bool>,
+    /// How many threads copy the public files into the output directory, one if unset
+    pub copy_concurrency: Option<usize>,
+    /// Html files relative to the crate, by the status code they are served for
+    pub error_pages: Option<BTreeMap<String, PathBuf>>,
+    /// Add a `Server-Timing` header with how long each request took to handle
+    pub server_timing: Option<bool>,
+    /// How long in milliseconds the page waits before reconnecting its dropped reload socket
+    pub reload_grace_ms: Option<u64>,
+    /// How long in milliseconds a rebuild runs before the pages show the building overlay
+    pub building_delay_ms: Option<u64>,
+    /// List the entries of directories of the output directory that have no index page
+    pub dir_listing: Option<bool>,
+    /// How long in milliseconds to wait after the app is ready before opening the browser
+    pub open_delay_ms: Option<u64>,
+    /// Response headers set or removed under a path prefix, over the ones set by the server
+    pub header_rules: Option<Vec<HeaderRuleConfig>>,
+    /// How the pages reload after a rebuild, a plain reload if unset
+    pub reload_style: Option<ReloadStyle>,
+    /// A command building the app into the output directory instead of cargo
+    pub build_command: Option<Vec<String>>,
+    /// What to do with a reload client that can't keep up with the messages, drop it if unset
+    pub slow_client_policy: Option<SlowClientPolicy>,
+    /// The line printed instead of `Dioxus @ v...` when serving starts, with the `{url}` (on
+    /// `localhost`), `{lan_url}` (on the network ip), `{version}` and `{branch}` placeholders
+    pub banner: Option<String>,
+    /// Canned responses served instead of the proxies and files, for working offline
+    pub mocks: Option<Vec<MockConfig>>,
+    /// The cargo target directory of the builds of `dx serve`, relative to the crate directory
+    pub target_dir: Option<PathBuf>,
+    /// The cargo feature enabled in the builds of `dx serve`, `dioxus-dev` by default
+    pub dev_feature: Option<String>,
+    /// Reload only the most recently focused page, the others reload once they're focused
+    pub reload_focused_only: Option<bool>,
+    /// A command and its arguments run on the dev page, getting it on stdin and printing the
+    /// transformed page
+    pub html_transform: Option<Vec<String>>,
+    /// Serve a zip of the output directory at `/_dioxus/bundle.zip`
+    pub bundle_download: Option<bool>,
+    /// Html inserted into the `<head>` of the dev page, or a file relative to the crate holding it
+    pub head_snippet: Option<String>,
+    /// Named sets of serve settings merged over these ones with `--serve-profile`
+    pub profiles: Option<BTreeMap<String, toml::Value>>,
+    /// Swap in a fresh output directory after every rebuild, so removed assets aren't served
+    /// anymore
+    pub clean_out_dir: Option<bool>,
+    /// Answer with 408 when handling a request takes longer, the reload sockets excepted
+    pub request_timeout_ms: Option<u64>,
+    /// Run `cargo check` before every build, to fail fast on code that doesn't compile
+    pub check_first: Option<bool>,
+    /// Deflate the templates sent over the hot reload websocket to the pages asking for it
+    pub ws_compression: Option<bool>,
+    /// Environment variables of the cargo process of serve builds, replacing those of the shell
+    pub build_env: Option<BTreeMap<String, String>>,
+    /// The `Host` headers answered, besides `localhost` and `127.0.0.1`, every host if unset
+    pub allowed_hosts: Option<Vec<String>>,
+}
+
+impl WebServeConfig {
+    /// The name of the generated entry page, `index.html` unless configured otherwise
+    pub fn index_file(&self) -> &str {
+        self.index_file.as_deref().unwrap_or("index.html")
+    }
+
+    /// The feature serve builds enable if the crate declares it, `dioxus-dev` unless configured
+    pub fn dev_feature(&self) -> &str {
+        self.dev_feature.as_deref().unwrap_or("dioxus-dev")
+    }
+
+    /// The html of `head_snippet`, read from the file it names if there is one
+    pub fn head_snippet(&self, crate_dir: &Path) -> String {
+        let Some(snippet) = &self.head_snippet else {
+            return String::new();
+        };
+        let file = crate_dir.join(snippet);
+        if file.is_file() {
+            match std::fs::read_to_string(&file) {
+                Ok(snippet) => return snippet,
+                Err(err) => log::warn!("Failed to read {}: {}", file.display(), err),
+            }
+        }
+        snippet.clone()
+    }
+
+    /// The html of the `error_pages` file of a status code, read again for every error so
+    /// edits apply right away
+    pub fn error_page(&self, crate_dir: &Path, status: u16) -> Option<String> {
+        let page = self.error_pages.as_ref()?.get(&status.to_string())?;
+        let path = crate_dir.join(page);
+        match std::fs::read_to_string(&path) {
+            Ok(html) => Some(html),
+            Err(err) => {
+                log::warn!("Failed to read the error page {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    /// Whether to show the building page, off unless configured or `background_build` is set
+    pub fn interstitial(&self) -> bool {
+        self.interstitial
+            .unwrap_or_else(|| self.background_build.unwrap_or(false))
+    }
+
+    /// The `dev_profile` settings as `--config` values of cargo for the given profile
+    pub fn dev_profile_overrides(&self, profile: &str) -> Vec<String> {
+        self.dev_profile
+            .iter()
+            .flatten()
+            .filter_map(|(key, value)| {
+                // Cargo parses the values as TOML
+                let value = match value {
+                    toml::Value::String(_)
+                    | toml::Value::Integer(_)
+                    | toml::Value::Float(_)
+                    | toml::Value::Boolean(_) => value.to_string(),
+                    _ => {
+                        log::warn!(
+                            "Ignoring web.serve.dev_profile.{}, it isn't a plain value",
+                            key
+                        );
+                        return None;
+                    }
+                };
+                Some(format!("profile.{}.{}={}", profile, key, value))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionLevel {
+    /// Cheapest on the CPU, best for serving over loopback
+    Fastest,
+    /// The default quality of each algorithm
+    Default,
+    /// Smallest responses, best for large bundles over a slow network
+    Best,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlowClientPolicy {
+    /// Disconnect the client, it reconnects and reloads once its connection recovers
+    Drop,
+    /// Keep waiting for the client, with a reload for the messages it missed
+    Wait,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReloadStyle {
+    /// Reload the page right away, it flashes white until it is painted again
+    Flash,
+    /// Dim the page before reloading and fade the reloaded page in on the same background
+    Soft,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderRuleConfig {
+    /// The start of the paths of the requests the rule applies to
+    pub path_prefix: String,
+    /// The headers to set, an empty value removes the header
+    pub headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockConfig {
+    /// The exact path of the requests to answer
+    pub path: String,
+    /// The method of the requests to answer, `GET` if unset
+    pub method: Option<String>,
+    /// The status of the response, 200 if unset
+    pub status: Option<u16>,
+    pub body: Option<String>,
+    /// The content type of the response, `application/json` if unset
+    pub content_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtraFileConfig {
+    /// The file to copy, relative to the crate directory
+    pub source: PathBuf,
+    /// Where to copy the file to, relative to the output directory
+    pub dest: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebServeAppConfig {
+    /// The crate directory of the app, relative to the current crate root
+    pub path: PathBuf,
+    /// The path prefix the app is served under, e.g. `/app-a`
+    pub prefix: String,
 }
 
 #[derive(Debug, Clone)]
@@ -185,10 +560,23 @@ pub struct CrateConfig {
     pub dioxus_config: DioxusConfig,
     pub release: bool,
     pub hot_reload: bool,
+    pub preview: bool,
+    pub dashboard: bool,
     pub cross_origin_policy: bool,
     pub verbose: bool,
+    pub debug_symbols: bool,
+    pub plugins_enabled: bool,
+    pub profile_build: bool,
+    pub show_diffs: bool,
+    pub fail_on_build_error: bool,
     pub custom_profile: Option<String>,
+    /// The `web.serve.profiles` entry applied with `--serve-profile`
+    pub serve_profile: Option<String>,
     pub features: Option<Vec<String>>,
+    /// Extra `--config` values passed to cargo
+    pub cargo_config: Vec<String>,
+    /// Extra environment variables of the cargo process
+    pub cargo_env: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone)]
@@ -268,10 +656,20 @@ impl CrateConfig {
             release,
             dioxus_config,
             hot_reload,
+            preview: false,
+            dashboard: false,
             cross_origin_policy: false,
             custom_profile,
+            serve_profile: None,
             features,
             verbose,
+            debug_symbols: false,
+            plugins_enabled: true,
+            profile_build: false,
+            show_diffs: false,
+            fail_on_build_error: false,
+            cargo_config: Vec::new(),
+            cargo_env: BTreeMap::new(),
         })
     }
 
@@ -290,6 +688,16 @@ impl CrateConfig {
         self
     }
 
+    pub fn with_preview(&mut self, preview: bool) -> &mut Self {
+        self.preview = preview;
+        self
+    }
+
+    pub fn with_dashboard(&mut self, dashboard: bool) -> &mut Self {
+        self.dashboard = dashboard;
+        self
+    }
+
     pub fn with_cross_origin_policy(&mut self, cross_origin_policy: bool) -> &mut Self {
         self.cross_origin_policy = cross_origin_policy;
         self
@@ -300,13 +708,111 @@ impl CrateConfig {
         self
     }
 
+    pub fn with_debug_symbols(&mut self, debug_symbols: bool) -> &mut Self {
+        self.debug_symbols = debug_symbols;
+        self
+    }
+
+    pub fn with_plugins_enabled(&mut self, plugins_enabled: bool) -> &mut Self {
+        self.plugins_enabled = plugins_enabled;
+        self
+    }
+
+    pub fn with_profile_build(&mut self, profile_build: bool) -> &mut Self {
+        self.profile_build = profile_build;
+        self
+    }
+
+    pub fn with_show_diffs(&mut self, show_diffs: bool) -> &mut Self {
+        self.show_diffs = show_diffs;
+        self
+    }
+
+    pub fn with_fail_on_build_error(&mut self, fail_on_build_error: bool) -> &mut Self {
+        self.fail_on_build_error = fail_on_build_error;
+        self
+    }
+
     pub fn set_profile(&mut self, profile: String) -> &mut Self {
         self.custom_profile = Some(profile);
         self
     }
 
+    pub fn set_serve_profile(&mut self, profile: String) -> &mut Self {
+        self.serve_profile = Some(profile);
+        self
+    }
+
     pub fn set_features(&mut self, features: Vec<String>) -> &mut Self {
         self.features = Some(features);
         self
     }
+
+    pub fn set_cargo_config(&mut self, cargo_config: Vec<String>) -> &mut Self {
+        self.cargo_config = cargo_config;
+        self
+    }
+
+    pub fn set_cargo_env(&mut self, cargo_env: BTreeMap<String, String>) -> &mut Self {
+        self.cargo_env = cargo_env;
+        self
+    }
+
+    /// The resolved config as TOML, `Dioxus.toml` with the profile and flags applied and a
+    /// `[serve]` table of what the flags set on their own, for `--print-config`
+    pub fn effective_config(&self) -> Result<String> {
+        let invalid = |err: toml::ser::Error| {
+            crate::Error::CustomError(format!("Failed to print the config: {}", err))
+        };
+        let mut config = toml::Value::try_from(&self.dioxus_config).map_err(invalid)?;
+
+        let mut serve = toml::value::Table::new();
+        let path = |path: &Path| toml::Value::String(path.display().to_string());
+        serve.insert("crate_dir".into(), path(&self.crate_dir));
+        serve.insert("out_dir".into(), path(&self.out_dir));
+        serve.insert("target_dir".into(), path(&self.target_dir));
+        serve.insert("profile".into(), self.profile_name().into());
+        for (flag, value) in [
+            ("release", self.release),
+            ("hot_reload", self.hot_reload),
+            ("preview", self.preview),
+            ("dashboard", self.dashboard),
+            ("cross_origin_policy", self.cross_origin_policy),
+            ("debug_symbols", self.debug_symbols),
+            ("plugins_enabled", self.plugins_enabled),
+            ("profile_build", self.profile_build),
+            ("show_diffs", self.show_diffs),
+            ("fail_on_build_error", self.fail_on_build_error),
+        ] {
+            serve.insert(flag.into(), value.into());
+        }
+        if let Some(features) = &self.features {
+            serve.insert(
+                "features".into(),
+                toml::Value::try_from(features).map_err(invalid)?,
+            );
+        }
+        serve.insert(
+            "cargo_config".into(),
+            toml::Value::try_from(&self.cargo_config).map_err(invalid)?,
+        );
+        serve.insert(
+            "cargo_env".into(),
+            toml::Value::try_from(&self.cargo_env).map_err(invalid)?,
+        );
+        if let toml::Value::Table(table) = &mut config {
+            table.insert("serve".into(), toml::Value::Table(serve));
+        }
+
+        toml::to_string_pretty(&config).map_err(invalid)
+    }
+
+    /// The name of the cargo profile the crate is built with
+    pub fn profile_name(&self) -> &str {
+        match &self.custom_profile {
+            Some(profile) => profile,
+            None if self.release => "release",
+            None => "dev",
+        }
+    }
 }
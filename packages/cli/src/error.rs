@@ -38,10 +38,67 @@ pub enum Error {
     #[error("Error proxying request: {0}")]
     ProxyRequestError(hyper::Error),
 
+    #[error(transparent)]
+    Serve(#[from] ServeError),
+
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
+/// Errors that stop `dx serve` from starting
+#[derive(ThisError, Debug)]
+pub enum ServeError {
+    #[error("mkcert is not installed. See https://github.com/FiloSottile/mkcert#installation for installation instructions, or set `web.https.mkcert = false` and provide your own certificates")]
+    MkcertMissing,
+
+    #[error("failed to generate mkcert certificates: {0}")]
+    MkcertFailed(#[source] std::io::Error),
+
+    #[error("only certificates generated by mkcert can be regenerated. Set `web.https.mkcert = true`, or replace your own certificates at `web.https.cert_path` and `web.https.key_path`")]
+    MkcertDisabled,
+
+    #[error("https is enabled but cert or key path is missing. Set both `web.https.cert_path` and `web.https.key_path`, or enable `web.https.mkcert`")]
+    TlsConfigIncomplete,
+
+    #[error("failed to load the https certificate or key: {0}")]
+    InvalidCertificate(#[source] std::io::Error),
+
+    #[error("failed to bind to {addr}: {source}. Is another server already running on this port? Try a different one with `--port`")]
+    BindFailed {
+        addr: std::net::SocketAddr,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("the server stopped unexpectedly: {0}")]
+    ServerFailed(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("dx serve is already running for this crate on port {port} (pid {pid}). Stop it first, or open the running server instead")]
+    AlreadyServing { pid: u32, port: u16 },
+
+    #[error("a plugin failed to start: {0}. Run with `--no-plugins` to serve without plugins")]
+    PluginFailed(#[source] anyhow::Error),
+
+    #[error("failed to create the output directory {path}: {source}. Check `application.out_dir` in Dioxus.toml")]
+    OutDirUnavailable {
+        path: std::path::PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("failed to set up the file watcher: {0}")]
+    WatcherSetupFailed(#[from] notify::Error),
+
+    #[error(
+        "failed to watch {path}: {source}. Check that the path in `web.watcher.watch_path` exists"
+    )]
+    WatchPathFailed {
+        path: std::path::PathBuf,
+        #[source]
+        source: notify::Error,
+    },
+}
+
 impl From<&str> for Error {
     fn from(s: &str) -> Self {
         Error::Unique(s.to_string())
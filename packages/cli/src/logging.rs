@@ -1,6 +1,121 @@
+use std::sync::RwLock;
+
 use fern::colors::{Color, ColoredLevelConfig};
+use log::LevelFilter;
+use serde::Deserialize;
+
+/// When to color the console output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Color a terminal, unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").map_or(true, |value| value.is_empty())
+                    && atty::is(atty::Stream::Stdout)
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Color the log lines and the console info only if the choice allows it
+pub fn set_color(choice: ColorChoice) {
+    colored::control::set_override(choice.enabled());
+}
+
+/// The CLI logs at info, the noisy dependencies only their warnings
+pub const DEFAULT_LOG_FILTER: &str =
+    "info,hyper=warn,h2=warn,tower=warn,tower_http=warn,axum=warn,notify=warn,mio=warn,rustls=warn";
+
+lazy_static::lazy_static! {
+    static ref LOG_FILTER: RwLock<LogFilter> =
+        RwLock::new(LogFilter::parse(DEFAULT_LOG_FILTER).unwrap());
+}
+
+/// Per-module log levels from directives like `RUST_LOG`'s, e.g. `info,hyper=debug`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    /// The level of each module, the most specific one applies
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    pub fn parse(directives: &str) -> Result<Self, String> {
+        let mut filter = Self {
+            default: LevelFilter::Info,
+            modules: Vec::new(),
+        };
+        for directive in directives.split(',').map(str::trim) {
+            if directive.is_empty() {
+                continue;
+            }
+            let (module, level) = match directive.split_once('=') {
+                Some((module, level)) => (
+                    Some(module.trim()),
+                    level
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("invalid log level in `{}`", directive))?,
+                ),
+                None => match directive.parse() {
+                    Ok(level) => (None, level),
+                    // A module on its own logs everything, like with `RUST_LOG`
+                    Err(_) => (Some(directive), LevelFilter::Trace),
+                },
+            };
+            match module {
+                Some(module) => filter.modules.push((module.replace('-', "_"), level)),
+                None => filter.default = level,
+            }
+        }
+        // The longest match is the most specific one
+        filter
+            .modules
+            .sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
+        Ok(filter)
+    }
+
+    /// The level a log target is filtered at
+    pub fn level(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .find(|(module, _)| {
+                target == module
+                    || target
+                        .strip_prefix(module.as_str())
+                        .map_or(false, |rest| rest.starts_with("::"))
+            })
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, LevelFilter::max)
+    }
+}
+
+/// Replace the log filter, [`DEFAULT_LOG_FILTER`] until this is called
+pub fn set_log_filter(filter: LogFilter) {
+    log::set_max_level(filter.max_level());
+    *LOG_FILTER.write().unwrap() = filter;
+}
 
 pub fn set_up_logging() {
+    set_color(ColorChoice::Auto);
+
     // configure colors for the whole line
     let colors_line = ColoredLevelConfig::new()
         .error(Color::Red)
@@ -18,6 +133,9 @@ pub fn set_up_logging() {
     // here we set up our fern Dispatch
     fern::Dispatch::new()
         .format(move |out, message, record| {
+            if !colored::control::SHOULD_COLORIZE.should_colorize() {
+                return out.finish(format_args!("[{}] {}", record.level(), message));
+            }
             out.finish(format_args!(
                 "{color_line}[{level}{color_line}] {message}\x1B[0m",
                 color_line = format_args!(
@@ -28,8 +146,31 @@ pub fn set_up_logging() {
                 message = message,
             ));
         })
-        .level(log::LevelFilter::Info)
+        .filter(|metadata| metadata.level() <= LOG_FILTER.read().unwrap().level(metadata.target()))
         .chain(std::io::stdout())
         .apply()
         .unwrap();
+    log::set_max_level(LOG_FILTER.read().unwrap().max_level());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn most_specific_module_level_applies() {
+        let filter =
+            LogFilter::parse("warn,dioxus-cli=debug,hyper=error,hyper::proto=trace").unwrap();
+        assert_eq!(filter.level("dioxus_cli::server"), LevelFilter::Debug);
+        assert_eq!(filter.level("hyper::client"), LevelFilter::Error);
+        assert_eq!(filter.level("hyper::proto::h1"), LevelFilter::Trace);
+        // Only whole module names match
+        assert_eq!(filter.level("hyperx"), LevelFilter::Warn);
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+        assert!(LogFilter::parse("hyper=loud").is_err());
+
+        let default = LogFilter::parse(DEFAULT_LOG_FILTER).unwrap();
+        assert_eq!(default.level("dioxus_cli::server"), LevelFilter::Info);
+        assert_eq!(default.level("notify::inotify"), LevelFilter::Warn);
+    }
 }
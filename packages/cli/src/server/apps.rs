@@ -0,0 +1,88 @@
+use axum::{response::Html, routing::get, Router};
+use tokio::sync::oneshot;
+use tower::util::BoxCloneService;
+
+use super::{finish_first_build, get_rustls, start_server, ServeHandle, ServeReady, WatchedApp};
+use crate::{CrateConfig, Result};
+
+/// An app served under a path prefix when `web.serve.apps` is configured
+pub struct ServedApp {
+    /// The path prefix, starting with a `/`
+    pub prefix: String,
+    pub config: CrateConfig,
+}
+
+/// Start one server for several apps.
+///
+/// Every app is built, watched and hot reloaded on its own and gets its own reload sockets
+/// under `{prefix}/_dioxus/`. The https and warmup settings come from the crate `dx serve` was
+/// started in.
+pub async fn startup_apps(
+    port: u16,
+    config: CrateConfig,
+    apps: Vec<ServedApp>,
+    start_browser: bool,
+    ready: Option<oneshot::Sender<ServeReady>>,
+) -> Result<()> {
+    let _lock = super::lock::ServeLock::acquire(&config, port)?;
+
+    let crate_config = config.clone();
+    let _ = ctrlc::set_handler(move || {
+        super::lock::release(&crate_config);
+        super::reset_dashboard();
+        std::process::exit(0);
+    });
+
+    let ip = super::get_ip().unwrap_or(String::from("0.0.0.0"));
+
+    // Before the first builds so it can stop if mkcert isn't installed or fails
+    let rustls_config = get_rustls(&config).await?;
+
+    let mut router = Router::new();
+    let mut index = String::from("<h1>Dioxus apps</h1><ul>");
+    // We got to own the apps so that their watchers exist for the duration of serve
+    let mut watched = Vec::new();
+    let mut served = Vec::new();
+
+    for app in apps {
+        // Built, watched and hot reloaded the same way as a single app
+        let (watched_app, first_build_result) = WatchedApp::watch(&ip, port, app.config).await?;
+        let initial_build =
+            finish_first_build(first_build_result, &ip, port, &watched_app.build_manager);
+        let app_router = watched_app
+            .router(initial_build, rustls_config.clone())
+            .await?;
+
+        index.push_str(&format!("<li><a href=\"{0}/\">{0}</a></li>", app.prefix));
+        // Boxing keeps axum from merging the app's routes and fallback into the outer router
+        router = router.nest(&app.prefix, BoxCloneService::new(app_router));
+        served.push(app.prefix);
+        watched.push(watched_app);
+    }
+
+    // After the first builds, which clear the console
+    for prefix in &served {
+        log::info!("📦 Serving {} at http://{}:{}{}/", prefix, ip, port, prefix);
+    }
+
+    index.push_str("</ul>");
+    router = router.route("/", get(move || async move { Html(index) }));
+
+    let build_managers = watched
+        .iter()
+        .map(|app| app.build_manager.clone())
+        .collect();
+    super::keys::listen(build_managers, ip.clone(), port);
+
+    log::info!("🚀 Starting development server for all apps...");
+    // The handle reloads every app, templates can't be pushed to one app in particular
+    let reload_txs = watched
+        .iter()
+        .map(|app| app.build_manager.reload_tx.clone())
+        .collect();
+    let ready = ready.map(|ready| (ready, ServeHandle::new(reload_txs, None)));
+    let start_browser = start_browser.then_some(None);
+    start_server(port, router, start_browser, rustls_config, &config, ready).await?;
+
+    Ok(())
+}
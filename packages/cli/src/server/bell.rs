@@ -0,0 +1,66 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+    process::{Child, Command, Stdio},
+};
+
+use crate::CrateConfig;
+
+/// Ring the terminal bell, or play the configured `watcher.reload_sound`, after a successful
+/// rebuild or hot reload if `watcher.bell_on_reload` is set.
+///
+/// Nothing is played when the output isn't a terminal, e.g. in CI logs.
+pub fn ring(config: &CrateConfig) {
+    let watcher = &config.dioxus_config.web.watcher;
+    if !watcher.bell_on_reload.unwrap_or(false) || !atty::is(atty::Stream::Stdout) {
+        return;
+    }
+
+    if let Some(sound) = &watcher.reload_sound {
+        let sound = config.crate_dir.join(sound);
+        match play(&sound) {
+            Ok(()) => return,
+            Err(err) => log::debug!("Failed to play {}: {}", sound.display(), err),
+        }
+    }
+
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(b"\x07");
+    let _ = stdout.flush();
+}
+
+/// Start playing a sound file with the player of the platform, without waiting for it
+fn play(sound: &Path) -> io::Result<()> {
+    let spawn = |program: &str, args: &[&str]| {
+        Command::new(program)
+            .args(args)
+            .arg(sound)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map(reap)
+    };
+
+    if cfg!(target_os = "macos") {
+        spawn("afplay", &[])
+    } else if cfg!(windows) {
+        let script = format!(
+            "(New-Object Media.SoundPlayer '{}').PlaySync()",
+            sound.display().to_string().replace('\'', "''")
+        );
+        Command::new("powershell")
+            .args(["-NoProfile", "-Command", &script])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map(reap)
+    } else {
+        // PulseAudio and PipeWire systems have paplay, plain ALSA ones only aplay
+        spawn("paplay", &[]).or_else(|_| spawn("aplay", &["-q"]))
+    }
+}
+
+/// Wait for the player in the background so it doesn't linger as a zombie process
+fn reap(mut child: Child) {
+    std::thread::spawn(move || child.wait());
+}
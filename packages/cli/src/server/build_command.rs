@@ -0,0 +1,103 @@
+use std::{
+    io::BufReader,
+    process::{Command, Stdio},
+    time::Instant,
+};
+
+use cargo_metadata::{
+    diagnostic::{Diagnostic, DiagnosticLevel},
+    Message,
+};
+
+use crate::{BuildResult, CrateConfig, Error, Result};
+
+/// Build with the `web.serve.build_command` instead of cargo, for wrappers like an xtask.
+///
+/// The command has to leave the app in the output directory. Lines it prints as cargo
+/// `--message-format=json` messages are read for warnings and errors, every other line is
+/// printed as is, and an exit code other than 0 fails the build.
+pub fn run_build_command(config: &CrateConfig, command: &[String]) -> Result<BuildResult> {
+    let args: Vec<String> = command
+        .iter()
+        .map(|arg| fill_placeholders(config, arg))
+        .collect();
+    let Some((program, args)) = args.split_first() else {
+        return Err(Error::BuildFailed(
+            "web.serve.build_command is empty".to_string(),
+        ));
+    };
+
+    log::info!("🚅 Running build command `{}`", command.join(" "));
+    let start = Instant::now();
+    let failed = |reason: String| Error::BuildFailed(format!("`{}` {}", program, reason));
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(&config.crate_dir)
+        .envs(&config.cargo_env)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|err| failed(format!("couldn't start: {}", err)))?;
+
+    let mut warnings: Vec<Diagnostic> = Vec::new();
+    let mut errors = Vec::new();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    for message in Message::parse_stream(stdout) {
+        match message? {
+            Message::CompilerMessage(msg) => match msg.message.level {
+                DiagnosticLevel::Error | DiagnosticLevel::Ice => {
+                    errors.push(msg.message.rendered.unwrap_or(msg.message.message))
+                }
+                DiagnosticLevel::Warning => warnings.push(msg.message),
+                _ => {}
+            },
+            Message::TextLine(line) => println!("{}", line),
+            _ => {}
+        }
+    }
+
+    let status = child.wait()?;
+    if !status.success() || !errors.is_empty() {
+        let mut reason = format!("failed with {}", status);
+        for error in errors {
+            reason.push('\n');
+            reason.push_str(&error);
+        }
+        return Err(failed(reason));
+    }
+    let elapsed_time = start.elapsed().as_millis();
+    Ok(BuildResult {
+        warnings,
+        elapsed_time,
+        phases: vec![("build command", elapsed_time)],
+    })
+}
+
+/// Replace `{out_dir}`, `{target_dir}`, `{crate_dir}` and `{profile}` in an argument
+fn fill_placeholders(config: &CrateConfig, arg: &str) -> String {
+    arg.replace("{out_dir}", &config.out_dir.display().to_string())
+        .replace("{target_dir}", &config.target_dir.display().to_string())
+        .replace("{crate_dir}", &config.crate_dir.display().to_string())
+        .replace("{profile}", config.profile_name())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn build_command_gets_the_placeholders_filled() {
+        let mut config = CrateConfig::new(None).unwrap();
+        config.out_dir = std::env::temp_dir().join("dioxus-build-command-out");
+        assert_eq!(
+            fill_placeholders(&config, "--out={out_dir}"),
+            format!("--out={}", config.out_dir.display())
+        );
+
+        let command = |script: &str| ["sh", "-c", script].map(String::from).to_vec();
+        assert!(run_build_command(&config, &command("test -n '{out_dir}'")).is_ok());
+        let err = run_build_command(&config, &command("exit 3")).unwrap_err();
+        assert!(err.to_string().contains("exit status: 3"), "{}", err);
+    }
+}
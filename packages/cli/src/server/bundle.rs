@@ -0,0 +1,117 @@
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+use axum::{
+    body::{Bytes, StreamBody},
+    http::{
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+        StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use tempfile::NamedTempFile;
+use tokio::io::AsyncReadExt;
+use zip::{write::FileOptions, ZipWriter};
+
+/// Serve a zip of the output directory at `/_dioxus/bundle.zip`.
+///
+/// The zip is written to a temporary file and streamed from there, so large builds aren't held
+/// in memory. The file is removed once the response is done with it, whether it was sent, the
+/// download was aborted or zipping failed.
+pub async fn bundle_handler(out_dir: PathBuf) -> Response {
+    let written = tokio::task::spawn_blocking(move || {
+        let zip_file = tempfile::Builder::new()
+            .prefix("dioxus-bundle-")
+            .suffix(".zip")
+            .tempfile()?;
+        write_zip(&out_dir, zip_file.as_file()).map_err(io::Error::from)?;
+        let file = zip_file.reopen()?;
+        Ok::<_, io::Error>((file, zip_file))
+    })
+    .await;
+    let (file, zip_file) = match written {
+        Ok(Ok(written)) => written,
+        Ok(Err(err)) => return zip_failed(err),
+        Err(err) => return zip_failed(io::Error::new(io::ErrorKind::Other, err)),
+    };
+
+    // The temporary file goes along with the stream, and is removed when it is dropped
+    let file = tokio::fs::File::from_std(file);
+    let chunks = futures::stream::unfold(Some((file, zip_file)), |state| async move {
+        let (mut file, zip_file) = state?;
+        let mut chunk = vec![0; 64 * 1024];
+        match file.read(&mut chunk).await {
+            Ok(0) => None,
+            Ok(read) => {
+                chunk.truncate(read);
+                Some((Ok(Bytes::from(chunk)), Some((file, zip_file))))
+            }
+            Err(err) => Some((Err(err), None)),
+        }
+    });
+
+    (
+        [
+            (CONTENT_TYPE, "application/zip"),
+            (CONTENT_DISPOSITION, "attachment; filename=\"bundle.zip\""),
+        ],
+        StreamBody::new(chunks),
+    )
+        .into_response()
+}
+
+fn zip_failed(err: io::Error) -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Failed to zip the build: {}", err),
+    )
+        .into_response()
+}
+
+/// Zip every file below the output directory into `zip_file`, with paths relative to it
+fn write_zip(out_dir: &Path, zip_file: &File) -> zip::result::ZipResult<()> {
+    let mut zip = ZipWriter::new(zip_file);
+    let options = FileOptions::default();
+    for entry in walkdir::WalkDir::new(out_dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+    {
+        let name = match entry.path().strip_prefix(out_dir) {
+            Ok(name) if !name.as_os_str().is_empty() => name.to_string_lossy().replace('\\', "/"),
+            _ => continue,
+        };
+        if entry.file_type().is_dir() {
+            zip.add_directory(name, options)?;
+        } else if entry.file_type().is_file() {
+            zip.start_file(name, options)?;
+            io::copy(&mut File::open(entry.path())?, &mut zip)?;
+        }
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bundles_contain_the_output_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let out_dir = dir.path().join("dist");
+        std::fs::create_dir_all(out_dir.join("assets")).unwrap();
+        std::fs::write(out_dir.join("index.html"), "<html></html>").unwrap();
+        std::fs::write(out_dir.join("assets/app.wasm"), "wasm").unwrap();
+
+        let zip_path = dir.path().join("bundle.zip");
+        write_zip(&out_dir, &File::create(&zip_path).unwrap()).unwrap();
+        let mut archive = zip::ZipArchive::new(File::open(&zip_path).unwrap()).unwrap();
+        let mut index = String::new();
+        io::Read::read_to_string(&mut archive.by_name("index.html").unwrap(), &mut index).unwrap();
+        assert_eq!(index, "<html></html>");
+        assert!(archive.by_name("assets/app.wasm").is_ok());
+    }
+}
@@ -0,0 +1,133 @@
+use std::{io, process::Command};
+
+use axum::http::{Method, StatusCode};
+use axum_server::tls_rustls::RustlsConfig;
+
+use super::{clean_out_dir_conflict, client_auth, mime, proxy, sni, watch_paths, ReloadTrigger};
+use crate::{CrateConfig, ServeError};
+
+/// Validate the serve configuration of a crate without building it or binding any port.
+///
+/// Returns a description of every problem found, which is empty if the config is fine.
+pub async fn check_config(config: &CrateConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if let Err(err) = check_tls(config).await {
+        problems.push(err.to_string());
+    }
+
+    for proxy_config in config.dioxus_config.web.proxy.iter().flatten() {
+        if let Err(err) = proxy::backend_uri(proxy_config) {
+            problems.push(err.to_string());
+        }
+        if let Err(err) = proxy::check_concurrency(proxy_config) {
+            problems.push(err.to_string());
+        }
+    }
+
+    for path in watch_paths(config, &ReloadTrigger::new(config)) {
+        if !path.exists() {
+            problems.push(format!("The watched path {} doesn't exist", path.display()));
+        }
+    }
+
+    let serve_config = &config.dioxus_config.web.serve;
+    if serve_config.clean_out_dir.unwrap_or(false) {
+        problems.extend(clean_out_dir_conflict(config));
+    }
+    for file in serve_config.extra_files.iter().flatten() {
+        let source = config.crate_dir.join(&file.source);
+        if !source.is_file() {
+            problems.push(format!("The extra file {} doesn't exist", source.display()));
+        }
+    }
+
+    for (extension, mime) in serve_config.mime_overrides.iter().flatten() {
+        if !mime::is_valid_mime(mime) {
+            problems.push(format!(
+                "{:?} isn't a valid MIME type for .{} files",
+                mime,
+                extension.trim_start_matches('.')
+            ));
+        }
+    }
+
+    for mock in serve_config.mocks.iter().flatten() {
+        if let Some(method) = &mock.method {
+            if Method::from_bytes(method.as_bytes()).is_err() {
+                problems.push(format!(
+                    "The mock of {} has an invalid method {:?}",
+                    mock.path, method
+                ));
+            }
+        }
+        if let Some(status) = mock.status {
+            if StatusCode::from_u16(status).is_err() {
+                problems.push(format!(
+                    "The mock of {} has an invalid status {}",
+                    mock.path, status
+                ));
+            }
+        }
+    }
+
+    for app in serve_config.apps.iter().flatten() {
+        if !config.crate_dir.join(&app.path).is_dir() {
+            problems.push(format!(
+                "The app directory {} served at {} doesn't exist",
+                app.path.display(),
+                app.prefix
+            ));
+        }
+    }
+
+    problems
+}
+
+/// The checks of `get_rustls` that don't generate or load anything for good
+async fn check_tls(config: &CrateConfig) -> Result<(), ServeError> {
+    let https = &config.dioxus_config.web.https;
+    if https.enabled != Some(true) {
+        return Ok(());
+    }
+
+    let certificates = https.certificates.as_deref().unwrap_or_default();
+    if !certificates.is_empty() {
+        // A generated mkcert certificate isn't there to check yet
+        let default = match https.mkcert {
+            Some(true) => None,
+            _ => https.cert_path.clone().zip(https.key_path.clone()),
+        };
+        return sni::sni_config(certificates, default, https.client_ca.as_deref())
+            .map(|_| ())
+            .map_err(ServeError::InvalidCertificate);
+    }
+
+    if let (Some(client_ca), Some(false), Some(cert), Some(key)) = (
+        &https.client_ca,
+        https.mkcert,
+        &https.cert_path,
+        &https.key_path,
+    ) {
+        return client_auth::client_auth_config(cert, key, client_ca)
+            .map(|_| ())
+            .map_err(ServeError::InvalidCertificate);
+    }
+
+    match https.mkcert {
+        // The certificates are generated when serving, so only check mkcert can run
+        Some(true) => match Command::new("mkcert").arg("-help").output() {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Err(ServeError::MkcertMissing),
+            Err(err) => Err(ServeError::MkcertFailed(err)),
+            Ok(_) => Ok(()),
+        },
+        Some(false) => match (&https.cert_path, &https.key_path) {
+            (Some(cert), Some(key)) => RustlsConfig::from_pem_file(cert, key)
+                .await
+                .map(|_| ())
+                .map_err(ServeError::InvalidCertificate),
+            _ => Err(ServeError::TlsConfigIncomplete),
+        },
+        None => Ok(()),
+    }
+}
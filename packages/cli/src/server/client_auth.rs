@@ -0,0 +1,65 @@
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    sync::Arc,
+};
+
+use rustls::{
+    server::{AllowAnyAuthenticatedClient, ClientCertVerifier},
+    Certificate, PrivateKey, RootCertStore,
+};
+use rustls_pemfile::Item;
+
+/// A TLS config that rejects clients without a certificate signed by the `web.https.client_ca`
+/// certificates, for testing mutual TLS locally
+pub fn client_auth_config(
+    cert_path: &str,
+    key_path: &str,
+    client_ca: &str,
+) -> io::Result<rustls::ServerConfig> {
+    let mut config = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_client_cert_verifier(client_verifier(client_ca)?)
+        .with_single_cert(read_certs(cert_path)?, read_key(key_path)?)
+        .map_err(invalid_data)?;
+    // The protocols `RustlsConfig::from_pem_file` offers
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+/// Accepts only clients with a certificate signed by one of the `client_ca` certificates
+pub fn client_verifier(client_ca: &str) -> io::Result<Arc<dyn ClientCertVerifier>> {
+    let mut roots = RootCertStore::empty();
+    for ca in read_certs(client_ca)? {
+        roots.add(&ca).map_err(invalid_data)?;
+    }
+    if roots.is_empty() {
+        return Err(invalid_data(format!("{} has no certificates", client_ca)));
+    }
+    Ok(AllowAnyAuthenticatedClient::new(roots).boxed())
+}
+
+pub fn read_certs(path: &str) -> io::Result<Vec<Certificate>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    Ok(rustls_pemfile::certs(&mut reader)?
+        .into_iter()
+        .map(Certificate)
+        .collect())
+}
+
+pub fn read_key(path: &str) -> io::Result<PrivateKey> {
+    let mut reader = BufReader::new(File::open(path)?);
+    loop {
+        match rustls_pemfile::read_one(&mut reader)? {
+            Some(Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key)) => {
+                return Ok(PrivateKey(key))
+            }
+            Some(_) => continue,
+            None => return Err(invalid_data(format!("{} has no private key", path))),
+        }
+    }
+}
+
+pub fn invalid_data(err: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use axum::{
+    routing::{get, MethodRouter},
+    Json, Router,
+};
+use serde::Serialize;
+
+/// A route of the dev server for tooling, listed at `/_dioxus`
+#[derive(Debug, Clone, Serialize)]
+pub struct ControlEndpoint {
+    pub path: &'static str,
+    pub method: &'static str,
+    pub description: &'static str,
+}
+
+/// The `/_dioxus/*` routes, each registered with a description for the `/_dioxus` index
+#[derive(Default)]
+pub struct ControlRoutes {
+    router: Router,
+    /// Kept apart, the layers of the http routes like compression would break their upgrades
+    sockets: Router,
+    endpoints: Vec<ControlEndpoint>,
+}
+
+impl ControlRoutes {
+    /// Add a `GET` route to the router and the index
+    pub fn get(
+        mut self,
+        path: &'static str,
+        description: &'static str,
+        route: MethodRouter,
+    ) -> Self {
+        self.router = self.router.route(path, route);
+        self.endpoints.push(ControlEndpoint {
+            path,
+            method: "GET",
+            description,
+        });
+        self
+    }
+
+    /// Add a `GET` route upgrading to a websocket to the router and the index
+    pub fn socket(
+        mut self,
+        path: &'static str,
+        description: &'static str,
+        route: MethodRouter,
+    ) -> Self {
+        self.sockets = self.sockets.route(path, route);
+        self.endpoints.push(ControlEndpoint {
+            path,
+            method: "GET",
+            description,
+        });
+        self
+    }
+
+    /// The router of the registered routes and the `/_dioxus` index of them, with `layer`
+    /// applied to all of them but the websockets
+    pub fn into_router(self, layer: impl FnOnce(Router) -> Router) -> Router {
+        let endpoints = Arc::new(self.endpoints);
+        let router = self.router.route(
+            "/_dioxus",
+            get(move || async move { Json(endpoints.as_ref().clone()) }),
+        );
+        layer(router).merge(self.sockets)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn index_lists_the_registered_routes() {
+        let router = ControlRoutes::default()
+            .get("/_dioxus/ping", "Answers pong", get(|| async { "pong" }))
+            .into_router(|router| router);
+        let (addr, handle) = crate::server::spawn_router(router);
+
+        let resp = hyper::Client::new()
+            .get(format!("http://{}/_dioxus", addr).parse().unwrap())
+            .await
+            .unwrap();
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        handle.abort();
+        let index: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            index,
+            serde_json::json!([
+                {"path": "/_dioxus/ping", "method": "GET", "description": "Answers pong"}
+            ])
+        );
+    }
+}
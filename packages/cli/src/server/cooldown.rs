@@ -0,0 +1,265 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// What the watcher should do with a requested rebuild
+#[derive(Debug, PartialEq, Eq)]
+pub enum CooldownDecision {
+    /// Rebuild now with these changed paths, and call [`RebuildCooldown::finish`] afterwards
+    Run(Vec<PathBuf>),
+    /// Call [`RebuildCooldown::take_pending`] once this much time has passed
+    Defer(Duration),
+    /// The change was merged into a rebuild that is already queued
+    Queued,
+}
+
+/// Enforces a minimum interval between the end of one full rebuild and the start of the next.
+///
+/// Changes that arrive during the cooldown, or while a rebuild is running, are collapsed into
+/// a single pending rebuild that runs once the interval has elapsed.
+#[derive(Debug)]
+pub struct RebuildCooldown {
+    interval: Duration,
+    settle: Duration,
+    running: bool,
+    last_finished: Option<Instant>,
+    pending: Option<Vec<PathBuf>>,
+}
+
+impl RebuildCooldown {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            settle: Duration::ZERO,
+            running: false,
+            last_finished: None,
+            pending: None,
+        }
+    }
+
+    /// Wait this long after the first change before rebuilding, so a burst of events from a
+    /// single edit is collapsed into one rebuild
+    pub fn with_settle(mut self, settle: Duration) -> Self {
+        self.settle = settle;
+        self
+    }
+
+    pub fn request(&mut self, now: Instant, changed: Vec<PathBuf>) -> CooldownDecision {
+        if self.running || self.pending.is_some() {
+            self.pending.get_or_insert_with(Vec::new).extend(changed);
+            return CooldownDecision::Queued;
+        }
+
+        match self.last_finished {
+            Some(last) if now.duration_since(last) < self.interval => {
+                self.pending = Some(changed);
+                let remaining = self.interval - now.duration_since(last);
+                CooldownDecision::Defer(remaining.max(self.settle))
+            }
+            _ if !self.settle.is_zero() => {
+                self.pending = Some(changed);
+                CooldownDecision::Defer(self.settle)
+            }
+            _ => {
+                self.running = true;
+                CooldownDecision::Run(changed)
+            }
+        }
+    }
+
+    /// Mark a build started outside of the watchers, like the initial build, as running so
+    /// the changes made meanwhile are queued behind it
+    pub fn start(&mut self) {
+        self.running = true;
+    }
+
+    /// Mark the running rebuild as done. If changes were queued in the meantime, returns how
+    /// long to wait before calling [`RebuildCooldown::take_pending`].
+    pub fn finish(&mut self, now: Instant) -> Option<Duration> {
+        self.running = false;
+        self.last_finished = Some(now);
+        self.pending
+            .is_some()
+            .then_some(self.interval.max(self.settle))
+    }
+
+    /// Start the pending rebuild, if there is one
+    pub fn take_pending(&mut self) -> Option<Vec<PathBuf>> {
+        let pending = self.pending.take()?;
+        self.running = true;
+        Some(pending)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Feed a change every `event_every` for `total`, rebuilding instantly, and return when
+    /// each rebuild ran
+    fn simulate(interval: Duration, event_every: Duration, total: Duration) -> Vec<Duration> {
+        let start = Instant::now();
+        let mut cooldown = RebuildCooldown::new(interval);
+        let mut timer: Option<Instant> = None;
+        let mut rebuilds = Vec::new();
+
+        let mut elapsed = Duration::ZERO;
+        while elapsed <= total {
+            let now = start + elapsed;
+
+            if let Some(fire_at) = timer.filter(|fire_at| *fire_at <= now) {
+                timer = None;
+                if cooldown.take_pending().is_some() {
+                    rebuilds.push(fire_at - start);
+                    timer = cooldown.finish(fire_at).map(|delay| fire_at + delay);
+                }
+            }
+
+            match cooldown.request(now, vec![PathBuf::from("src/main.rs")]) {
+                CooldownDecision::Run(_) => {
+                    rebuilds.push(elapsed);
+                    timer = cooldown.finish(now).map(|delay| now + delay);
+                }
+                CooldownDecision::Defer(delay) => timer = Some(now + delay),
+                CooldownDecision::Queued => {}
+            }
+
+            elapsed += event_every;
+        }
+
+        rebuilds
+    }
+
+    #[test]
+    fn caps_rebuild_frequency() {
+        let interval = Duration::from_millis(500);
+        let rebuilds = simulate(
+            interval,
+            Duration::from_millis(50),
+            Duration::from_millis(2000),
+        );
+
+        assert!(rebuilds.len() <= 5, "too many rebuilds: {:?}", rebuilds);
+        for pair in rebuilds.windows(2) {
+            assert!(pair[1] - pair[0] >= interval, "{:?}", rebuilds);
+        }
+    }
+
+    #[test]
+    fn continuous_edits_keep_rebuilding() {
+        let rebuilds = simulate(
+            Duration::from_millis(500),
+            Duration::from_millis(50),
+            Duration::from_millis(2000),
+        );
+
+        // The cap must not starve rebuilds while changes keep coming in
+        assert!(rebuilds.len() >= 4, "too few rebuilds: {:?}", rebuilds);
+    }
+
+    #[test]
+    fn changes_are_queued_while_running() {
+        let now = Instant::now();
+        let mut cooldown = RebuildCooldown::new(Duration::ZERO);
+
+        assert_eq!(
+            cooldown.request(now, vec![PathBuf::from("a.rs")]),
+            CooldownDecision::Run(vec![PathBuf::from("a.rs")])
+        );
+        assert_eq!(
+            cooldown.request(now, vec![PathBuf::from("b.rs")]),
+            CooldownDecision::Queued
+        );
+        assert_eq!(
+            cooldown.request(now, vec![PathBuf::from("c.rs")]),
+            CooldownDecision::Queued
+        );
+        assert_eq!(cooldown.finish(now), Some(Duration::ZERO));
+        assert_eq!(
+            cooldown.take_pending(),
+            Some(vec![PathBuf::from("b.rs"), PathBuf::from("c.rs")])
+        );
+        assert_eq!(cooldown.finish(now), None);
+    }
+
+    #[test]
+    fn changes_wait_for_the_initial_build() {
+        let now = Instant::now();
+        let mut cooldown = RebuildCooldown::new(Duration::ZERO);
+
+        cooldown.start();
+        assert_eq!(
+            cooldown.request(now, vec![PathBuf::from("a.rs")]),
+            CooldownDecision::Queued
+        );
+        assert_eq!(cooldown.finish(now), Some(Duration::ZERO));
+        assert_eq!(cooldown.take_pending(), Some(vec![PathBuf::from("a.rs")]));
+        assert_eq!(cooldown.finish(now), None);
+    }
+
+    #[test]
+    fn burst_is_coalesced_into_one_rebuild() {
+        let start = Instant::now();
+        let settle = Duration::from_millis(100);
+        let mut cooldown = RebuildCooldown::new(Duration::ZERO).with_settle(settle);
+
+        // A single save firing several events in quick succession
+        assert_eq!(
+            cooldown.request(start, vec![PathBuf::from("src/main.rs")]),
+            CooldownDecision::Defer(settle)
+        );
+        for millis in 1..10 {
+            assert_eq!(
+                cooldown.request(
+                    start + Duration::from_millis(millis),
+                    vec![PathBuf::from("src/main.rs")]
+                ),
+                CooldownDecision::Queued
+            );
+        }
+
+        let changed = cooldown.take_pending().unwrap();
+        assert_eq!(changed.len(), 10);
+        assert_eq!(cooldown.finish(start + settle), None);
+        assert_eq!(cooldown.take_pending(), None);
+    }
+
+    #[test]
+    fn changes_while_running_are_coalesced() {
+        let start = Instant::now();
+        let settle = Duration::from_millis(100);
+        let mut cooldown = RebuildCooldown::new(Duration::ZERO).with_settle(settle);
+
+        cooldown.request(start, vec![PathBuf::from("a.rs")]);
+        assert!(cooldown.take_pending().is_some());
+
+        // Events during the rebuild start exactly one follow-up rebuild
+        for _ in 0..5 {
+            assert_eq!(
+                cooldown.request(start, vec![PathBuf::from("b.rs")]),
+                CooldownDecision::Queued
+            );
+        }
+        assert_eq!(cooldown.finish(start), Some(settle));
+        assert_eq!(
+            cooldown.take_pending().map(|changed| changed.len()),
+            Some(5)
+        );
+        assert_eq!(cooldown.finish(start), None);
+    }
+
+    #[test]
+    fn no_interval_runs_immediately() {
+        let now = Instant::now();
+        let mut cooldown = RebuildCooldown::new(Duration::ZERO);
+
+        for _ in 0..3 {
+            assert!(matches!(
+                cooldown.request(now, vec![]),
+                CooldownDecision::Run(_)
+            ));
+            assert_eq!(cooldown.finish(now), None);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+
+use dioxus_core::Template;
+use dioxus_html::HtmlCtx;
+use dioxus_rsx::hot_reload::{FileMap, FileMapBuildResult};
+
+use crate::CrateConfig;
+
+/// The rsx of every hot reloaded crate, the served crate and the `watcher.crates`.
+///
+/// Each crate keeps a map of its own, as a map rebuilds itself from its crate directory when
+/// a new file shows up.
+#[derive(Default)]
+pub struct CrateFileMaps {
+    maps: Vec<(PathBuf, FileMap<HtmlCtx>)>,
+}
+
+impl CrateFileMaps {
+    pub fn create(config: &CrateConfig) -> Self {
+        let maps = std::iter::once(config.crate_dir.clone())
+            .chain(hot_reload_crates(config))
+            .filter_map(|crate_dir| {
+                let FileMapBuildResult { map, errors } =
+                    match FileMap::<HtmlCtx>::create(crate_dir.clone()) {
+                        Ok(result) => result,
+                        Err(err) => {
+                            log::error!("Failed to read {}: {}", crate_dir.display(), err);
+                            return None;
+                        }
+                    };
+                for err in errors {
+                    log::error!("{}", err);
+                }
+                Some((crate_dir, map))
+            })
+            .collect();
+        Self { maps }
+    }
+
+    /// The templates changed since the last build, in every crate
+    pub fn templates(&self) -> Vec<Template<'static>> {
+        self.maps
+            .iter()
+            .flat_map(|(_, map)| map.map.values())
+            .filter_map(|(_, template_slot)| *template_slot)
+            .collect()
+    }
+
+    /// The directory and map of the crate a file belongs to, the innermost for nested crates
+    pub fn crate_of(&mut self, path: &Path) -> Option<(&Path, &mut FileMap<HtmlCtx>)> {
+        self.maps
+            .iter_mut()
+            .filter(|(crate_dir, _)| path.starts_with(crate_dir))
+            .max_by_key(|(crate_dir, _)| crate_dir.components().count())
+            .map(|(crate_dir, map)| (crate_dir.as_path(), map))
+    }
+}
+
+/// The other crates whose rsx hot reloads: the `watcher.crates`, or else the path
+/// dependencies of the crate in its workspace
+pub fn hot_reload_crates(config: &CrateConfig) -> Vec<PathBuf> {
+    let canonical = |path: &PathBuf| config.crate_dir.join(path).canonicalize().ok();
+    let crates: Vec<PathBuf> = match &config.dioxus_config.web.watcher.crates {
+        Some(crates) => crates.iter().filter_map(canonical).collect(),
+        None => {
+            let workspace_dir = config
+                .workspace_dir
+                .canonicalize()
+                .unwrap_or_else(|_| config.workspace_dir.clone());
+            config
+                .manifest
+                .dependencies
+                .values()
+                .filter_map(|dependency| dependency.detail()?.path.as_ref())
+                .filter_map(|path| canonical(&PathBuf::from(path)))
+                .filter(|path| path.starts_with(&workspace_dir))
+                .collect()
+        }
+    };
+    let crate_dir = config.crate_dir.canonicalize().ok();
+    crates
+        .into_iter()
+        .filter(|path| Some(path) != crate_dir.as_ref())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn files_belong_to_the_innermost_crate() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let nested = root.join("components");
+        std::fs::create_dir_all(&nested).unwrap();
+        let map = |dir: &Path| FileMap::<HtmlCtx>::create(dir.to_path_buf()).unwrap().map;
+        let mut maps = CrateFileMaps {
+            maps: vec![(root.clone(), map(&root)), (nested.clone(), map(&nested))],
+        };
+
+        let crate_of = |maps: &mut CrateFileMaps, path: &Path| {
+            maps.crate_of(path)
+                .map(|(crate_dir, _)| crate_dir.to_path_buf())
+        };
+        assert_eq!(
+            crate_of(&mut maps, &root.join("src/main.rs")),
+            Some(root.clone())
+        );
+        assert_eq!(
+            crate_of(&mut maps, &nested.join("src/lib.rs")),
+            Some(nested.clone())
+        );
+        assert_eq!(crate_of(&mut maps, Path::new("/elsewhere/lib.rs")), None);
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+lazy_static::lazy_static! {
+    /// The contents of the watched `.rs` files as of the last rebuild, for `--show-diffs`
+    static ref SNAPSHOTS: Mutex<HashMap<PathBuf, String>> = Mutex::new(HashMap::new());
+}
+
+fn is_rust_file(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| ext == "rs")
+}
+
+/// Remember the contents of the `.rs` files below the watched paths
+pub fn snapshot(paths: &[PathBuf]) {
+    let mut snapshots = SNAPSHOTS.lock().unwrap();
+    for entry in paths
+        .iter()
+        .flat_map(walkdir::WalkDir::new)
+        .filter_map(|entry| entry.ok())
+    {
+        let path = entry.path();
+        if entry.file_type().is_file() && is_rust_file(path) {
+            if let Ok(contents) = std::fs::read_to_string(path) {
+                snapshots.insert(path.to_path_buf(), contents);
+            }
+        }
+    }
+}
+
+/// How many lines of a changed `.rs` file were added and removed since the last call, or
+/// since the snapshot.
+///
+/// Returns `None` for other files and files without a previous version.
+pub fn diff_stat(path: &Path) -> Option<(usize, usize)> {
+    if !is_rust_file(path) {
+        return None;
+    }
+    let contents = std::fs::read_to_string(path).ok()?;
+    let previous = SNAPSHOTS
+        .lock()
+        .unwrap()
+        .insert(path.to_path_buf(), contents.clone())?;
+    Some(count_changes(&previous, &contents))
+}
+
+/// Count the lines only one of the versions has, ignoring where they moved
+fn count_changes(old: &str, new: &str) -> (usize, usize) {
+    let mut counts: HashMap<&str, isize> = HashMap::new();
+    for line in old.lines() {
+        *counts.entry(line).or_default() -= 1;
+    }
+    for line in new.lines() {
+        *counts.entry(line).or_default() += 1;
+    }
+    counts.values().fold((0, 0), |(added, removed), &count| {
+        if count > 0 {
+            (added + count as usize, removed)
+        } else {
+            (added, removed + count.unsigned_abs())
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn changed_lines_are_counted() {
+        let old = "fn main() {\n    println!(\"a\");\n}\n";
+        let new = "fn main() {\n    println!(\"b\");\n    println!(\"c\");\n}\n";
+        assert_eq!(count_changes(old, new), (2, 1));
+        assert_eq!(count_changes(old, old), (0, 0));
+    }
+}
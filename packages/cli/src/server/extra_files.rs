@@ -0,0 +1,61 @@
+use std::path::{Path, PathBuf};
+
+use crate::CrateConfig;
+
+/// The configured `web.serve.extra_files`, as absolute source and destination paths
+fn extra_files(config: &CrateConfig) -> impl Iterator<Item = (PathBuf, PathBuf)> + '_ {
+    config
+        .dioxus_config
+        .web
+        .serve
+        .extra_files
+        .iter()
+        .flatten()
+        .map(|file| {
+            (
+                config.crate_dir.join(&file.source),
+                config.out_dir.join(&file.dest),
+            )
+        })
+}
+
+/// The source files that need to be watched
+pub fn sources(config: &CrateConfig) -> Vec<PathBuf> {
+    extra_files(config).map(|(source, _)| source).collect()
+}
+
+pub fn is_source(config: &CrateConfig, path: &Path) -> bool {
+    extra_files(config).any(|(source, _)| source == path)
+}
+
+/// Copy every extra file into the output directory, so it survives the build clobbering it
+pub fn copy_all(config: &CrateConfig) {
+    for (source, dest) in extra_files(config) {
+        if let Err(err) = copy(&source, &dest) {
+            log::error!(
+                "Failed to copy {} to {}: {}",
+                source.display(),
+                dest.display(),
+                err
+            );
+        }
+    }
+}
+
+/// Copy the extra files with any of these sources
+pub fn copy_changed(config: &CrateConfig, changed: &[PathBuf]) {
+    for (source, dest) in extra_files(config) {
+        if changed.contains(&source) {
+            if let Err(err) = copy(&source, &dest) {
+                log::error!("Failed to copy {}: {}", source.display(), err);
+            }
+        }
+    }
+}
+
+fn copy(source: &Path, dest: &Path) -> std::io::Result<()> {
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(source, dest).map(|_| ())
+}
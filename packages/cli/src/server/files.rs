@@ -0,0 +1,360 @@
+use std::{io, path::PathBuf, sync::Arc};
+
+use axum::{
+    body::{boxed, Body, BoxBody},
+    http::{Request, Response},
+};
+use futures::future::BoxFuture;
+use tower::ServiceExt;
+use tower_http::services::ServeDir;
+
+use crate::CrateConfig;
+
+/// Where the router of `dx serve` gets the files of the app from, the output directory unless
+/// a test serves them from memory
+pub trait FileSource: Send + Sync + 'static {
+    /// Answer a request for a file, with a 404 if there is none
+    fn serve(&self, req: Request<Body>) -> BoxFuture<'static, io::Result<Response<BoxBody>>>;
+
+    /// The contents of a file, by its path relative to the root of the app
+    fn read(&self, path: &str) -> BoxFuture<'static, io::Result<Vec<u8>>>;
+
+    /// Whether there is anything to serve, false while the output directory is missing
+    fn available(&self) -> bool;
+
+    /// The entries of a directory, by its path relative to the root of the app
+    fn list(&self, path: &str) -> BoxFuture<'static, io::Result<Vec<DirEntry>>>;
+}
+
+/// A file or directory in a directory of the app
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// The files of the output directory
+pub struct DiskFiles {
+    root: PathBuf,
+    serve_dir: ServeDir,
+}
+
+impl DiskFiles {
+    pub fn shared(config: &CrateConfig) -> Arc<dyn FileSource> {
+        let root = config.crate_dir.join(&config.out_dir);
+        Arc::new(Self {
+            serve_dir: ServeDir::new(&root),
+            root,
+        })
+    }
+}
+
+impl FileSource for DiskFiles {
+    fn serve(&self, req: Request<Body>) -> BoxFuture<'static, io::Result<Response<BoxBody>>> {
+        let serve_dir = self.serve_dir.clone();
+        Box::pin(async move {
+            let response = serve_dir.oneshot(req).await?;
+            Ok(response.map(boxed))
+        })
+    }
+
+    fn read(&self, path: &str) -> BoxFuture<'static, io::Result<Vec<u8>>> {
+        let path = self.root.join(path);
+        Box::pin(async move { tokio::fs::read(path).await })
+    }
+
+    fn available(&self) -> bool {
+        self.root.is_dir()
+    }
+
+    fn list(&self, path: &str) -> BoxFuture<'static, io::Result<Vec<DirEntry>>> {
+        let path = self.root.join(path);
+        Box::pin(async move {
+            let mut entries = Vec::new();
+            let mut dir = tokio::fs::read_dir(path).await?;
+            while let Some(entry) = dir.next_entry().await? {
+                entries.push(DirEntry {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    is_dir: entry.file_type().await?.is_dir(),
+                });
+            }
+            Ok(entries)
+        })
+    }
+}
+
+/// Files kept in memory, to test the router without building a project
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryFiles {
+    /// The content type and contents of each path, without the leading `/`
+    files: std::collections::HashMap<String, (&'static str, Vec<u8>)>,
+}
+
+#[cfg(test)]
+impl MemoryFiles {
+    pub fn with_file(mut self, path: &str, content_type: &'static str, contents: &str) -> Self {
+        self.files.insert(
+            path.to_string(),
+            (content_type, contents.as_bytes().to_vec()),
+        );
+        self
+    }
+}
+
+#[cfg(test)]
+impl FileSource for MemoryFiles {
+    fn serve(&self, req: Request<Body>) -> BoxFuture<'static, io::Result<Response<BoxBody>>> {
+        use axum::http::{header::CONTENT_TYPE, StatusCode};
+
+        // Directories are answered with their index, like `ServeDir` does
+        let mut path = req.uri().path().trim_start_matches('/').to_string();
+        if path.is_empty() || path.ends_with('/') {
+            path.push_str("index.html");
+        }
+        let response = match self.files.get(&path) {
+            Some((content_type, contents)) => Response::builder()
+                .header(CONTENT_TYPE, *content_type)
+                .body(boxed(Body::from(contents.clone()))),
+            None => Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(boxed(Body::empty())),
+        };
+        let response = response.map_err(|err| io::Error::new(io::ErrorKind::Other, err));
+        Box::pin(async move { response })
+    }
+
+    fn read(&self, path: &str) -> BoxFuture<'static, io::Result<Vec<u8>>> {
+        let contents = self
+            .files
+            .get(path)
+            .map(|(_, contents)| contents.clone())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound));
+        Box::pin(async move { contents })
+    }
+
+    fn available(&self) -> bool {
+        true
+    }
+
+    fn list(&self, path: &str) -> BoxFuture<'static, io::Result<Vec<DirEntry>>> {
+        // Directories only exist as the prefixes of the paths of files
+        let prefix = match path.trim_matches('/') {
+            "" => String::new(),
+            dir => format!("{}/", dir),
+        };
+        let mut entries: Vec<DirEntry> = Vec::new();
+        for file in self.files.keys() {
+            let Some(rest) = file.strip_prefix(&prefix) else {
+                continue;
+            };
+            let entry = match rest.split_once('/') {
+                Some((dir, _)) => DirEntry {
+                    name: dir.to_string(),
+                    is_dir: true,
+                },
+                None => DirEntry {
+                    name: rest.to_string(),
+                    is_dir: false,
+                },
+            };
+            if !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+        let entries = match entries.is_empty() {
+            true => Err(io::Error::from(io::ErrorKind::NotFound)),
+            false => Ok(entries),
+        };
+        Box::pin(async move { entries })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    };
+
+    use axum::http::{header::CONTENT_TYPE, StatusCode};
+    use tokio::sync::broadcast;
+
+    use crate::server::{
+        setup_router,
+        status::{BuildState, BuildStatus},
+        WsReloadState,
+    };
+
+    /// Request a path of the router of `dx serve` serving an index page and a script from memory
+    async fn fetch(
+        configure: impl FnOnce(&mut CrateConfig),
+        initial_build: Option<Arc<AtomicBool>>,
+        path: &str,
+    ) -> (Response<()>, String) {
+        let files = MemoryFiles::default()
+            .with_file("index.html", "text/html", "<html>index</html>")
+            .with_file("app.html", "text/html", "<html>app</html>")
+            .with_file("assets/app.js", "text/javascript", "app()")
+            .with_file("assets/icons/logo.svg", "image/svg+xml", "<svg></svg>")
+            // What `ServeDir` guesses where the system has no mime type for wasm
+            .with_file(
+                "assets/dioxus/app_bg.wasm",
+                "application/octet-stream",
+                "\0asm",
+            );
+        let mut config = CrateConfig::new(None).unwrap();
+        configure(&mut config);
+
+        let (reload_tx, _) = broadcast::channel(1);
+        let ws_reload = Arc::new(WsReloadState::new(reload_tx, &config));
+        let build_status = Arc::new(Mutex::new(BuildStatus::new(BuildState::Ok)));
+        let router = setup_router(
+            config,
+            Arc::new(files),
+            ws_reload,
+            None,
+            initial_build,
+            build_status,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let req = Request::get(path).body(Body::empty()).unwrap();
+        let (parts, body) = router.oneshot(req).await.unwrap().into_parts();
+        let body = hyper::body::to_bytes(body).await.unwrap();
+        (
+            Response::from_parts(parts, ()),
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn files_get_the_cross_origin_headers() {
+        let (resp, body) = fetch(
+            |config| config.cross_origin_policy = true,
+            None,
+            "/assets/app.js",
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(body, "app()");
+        assert_eq!(resp.headers()[CONTENT_TYPE], "text/javascript");
+        assert_eq!(
+            resp.headers()["cross-origin-embedder-policy"],
+            "require-corp"
+        );
+        assert_eq!(resp.headers()["cross-origin-opener-policy"], "same-origin");
+    }
+
+    #[tokio::test]
+    async fn the_index_route_gets_the_cross_origin_headers() {
+        let custom_index = |config: &mut CrateConfig| {
+            config.cross_origin_policy = true;
+            config.dioxus_config.web.serve.index_file = Some("app.html".into());
+        };
+        let (resp, body) = fetch(custom_index, None, "/").await;
+        assert_eq!(body, "<html>app</html>");
+        assert_eq!(
+            resp.headers()["cross-origin-embedder-policy"],
+            "require-corp"
+        );
+        assert_eq!(resp.headers()["cross-origin-opener-policy"], "same-origin");
+    }
+
+    #[tokio::test]
+    async fn the_favicon_gets_the_cross_origin_headers() {
+        let (resp, _) = fetch(
+            |config| config.cross_origin_policy = true,
+            None,
+            "/favicon.ico",
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            resp.headers()["cross-origin-embedder-policy"],
+            "require-corp"
+        );
+        assert_eq!(resp.headers()["cross-origin-opener-policy"], "same-origin");
+    }
+
+    #[tokio::test]
+    async fn wasm_is_served_as_application_wasm_by_the_router() {
+        let (resp, _) = fetch(|_| {}, None, "/assets/dioxus/app_bg.wasm").await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[CONTENT_TYPE], "application/wasm");
+
+        // The other layers of the file service keep the content type
+        let layered = |config: &mut CrateConfig| {
+            config.cross_origin_policy = true;
+            config.dioxus_config.web.serve.compression_level = Some(crate::CompressionLevel::Best);
+            config.dioxus_config.web.serve.server_timing = Some(true);
+        };
+        let (resp, _) = fetch(layered, None, "/assets/dioxus/app_bg.wasm").await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[CONTENT_TYPE], "application/wasm");
+        assert_eq!(
+            resp.headers()["cross-origin-embedder-policy"],
+            "require-corp"
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_routes_fall_back_to_the_index() {
+        let index_on_404 = |on: bool| {
+            move |config: &mut CrateConfig| config.dioxus_config.web.watcher.index_on_404 = Some(on)
+        };
+        let (resp, body) = fetch(index_on_404(true), None, "/users/1").await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(body, "<html>index</html>");
+
+        let (resp, _) = fetch(index_on_404(false), None, "/users/1").await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn directories_without_an_index_are_listed() {
+        let dir_listing =
+            |config: &mut CrateConfig| config.dioxus_config.web.serve.dir_listing = Some(true);
+        let (resp, body) = fetch(dir_listing, None, "/assets/").await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(body.contains("href=\"icons/\""));
+        assert!(body.contains("href=\"app.js\""));
+
+        let (resp, _) = fetch(|_| {}, None, "/assets/").await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn building_page_is_shown_until_the_first_build() {
+        let building = Arc::new(AtomicBool::new(false));
+        let interstitial =
+            |config: &mut CrateConfig| config.dioxus_config.web.serve.interstitial = Some(true);
+        let (resp, _) = fetch(interstitial, Some(building.clone()), "/").await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        building.store(true, Ordering::SeqCst);
+        let (resp, body) = fetch(interstitial, Some(building), "/").await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(body, "<html>index</html>");
+    }
+
+    #[tokio::test]
+    async fn missing_files_get_the_configured_error_page() {
+        let dir = tempfile::tempdir().unwrap();
+        let page = dir.path().join("404.html");
+        std::fs::write(&page, "<html>not found</html>").unwrap();
+        let error_pages = |config: &mut CrateConfig| {
+            config.dioxus_config.web.watcher.index_on_404 = Some(false);
+            config.dioxus_config.web.serve.error_pages =
+                Some([("404".to_string(), page.clone())].into_iter().collect());
+        };
+        let (resp, body) = fetch(error_pages, None, "/missing.js").await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(resp.headers()[CONTENT_TYPE], "text/html");
+        assert_eq!(body, "<html>not found</html>");
+    }
+}
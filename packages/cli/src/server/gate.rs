@@ -0,0 +1,61 @@
+use std::process::Command;
+
+use crate::{CrateConfig, Result};
+
+/// Run the `web.watcher.gate_command` after a successful rebuild, so the pages only reload
+/// for code that passes it.
+///
+/// Passes without a configured command.
+pub fn run_gate(config: &CrateConfig) -> Result<()> {
+    let Some(command) = config.dioxus_config.web.watcher.gate_command.as_ref() else {
+        return Ok(());
+    };
+    let Some((program, args)) = command.split_first() else {
+        return Ok(());
+    };
+
+    log::info!("🚦 Running the gate command `{}`", command.join(" "));
+    let failed = |reason: String| {
+        crate::error::Error::CustomError(format!(
+            "The gate command `{}` failed: {}",
+            command.join(" "),
+            reason
+        ))
+    };
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(&config.crate_dir)
+        .output()
+        .map_err(|err| failed(err.to_string()))?;
+    if !output.status.success() {
+        // Test runners and linters print their failures to either stream
+        let mut printed = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.trim().is_empty() {
+            printed.push('\n');
+            printed.push_str(stderr.trim());
+        }
+        return Err(failed(format!("{}\n{}", output.status, printed.trim())));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gate(command: &[&str]) -> Result<()> {
+        let mut config = CrateConfig::new(None).unwrap();
+        config.dioxus_config.web.watcher.gate_command =
+            Some(command.iter().map(|arg| arg.to_string()).collect());
+        run_gate(&config)
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn failing_gate_reports_its_output() {
+        assert!(gate(&["true"]).is_ok());
+        let err = gate(&["sh", "-c", "echo 2 tests failed; exit 1"]).unwrap_err();
+        assert!(err.to_string().contains("2 tests failed"));
+    }
+}
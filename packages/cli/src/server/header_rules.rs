@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use axum::{
+    http::{HeaderName, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::CrateConfig;
+
+/// The `web.serve.header_rules`, setting or removing response headers under a path prefix
+#[derive(Debug, Default)]
+pub struct HeaderRules {
+    rules: Vec<HeaderRule>,
+}
+
+#[derive(Debug)]
+struct HeaderRule {
+    path_prefix: String,
+    /// The headers to set, and to remove where the value is unset
+    headers: Vec<(HeaderName, Option<HeaderValue>)>,
+}
+
+impl HeaderRules {
+    pub fn from_config(config: &CrateConfig) -> Self {
+        let rules = config
+            .dioxus_config
+            .web
+            .serve
+            .header_rules
+            .iter()
+            .flatten()
+            .map(|rule| HeaderRule {
+                path_prefix: rule.path_prefix.clone(),
+                headers: rule
+                    .headers
+                    .iter()
+                    .filter_map(|(name, value)| match parse_header(name, value) {
+                        Ok(header) => Some(header),
+                        Err(err) => {
+                            log::warn!(
+                                "Ignoring the header {} of the rule for {}: {}",
+                                name,
+                                rule.path_prefix,
+                                err
+                            );
+                            None
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// The headers of the rules matching a path, in the order they apply
+    fn headers_for<'a>(
+        &'a self,
+        path: &'a str,
+    ) -> impl Iterator<Item = &'a (HeaderName, Option<HeaderValue>)> + 'a {
+        self.rules
+            .iter()
+            .filter(move |rule| path.starts_with(&rule.path_prefix))
+            .flat_map(|rule| &rule.headers)
+    }
+}
+
+/// An empty value removes the header, e.g. the cross origin policy from embedded pages
+fn parse_header(name: &str, value: &str) -> Result<(HeaderName, Option<HeaderValue>), String> {
+    let name = HeaderName::from_bytes(name.as_bytes()).map_err(|err| err.to_string())?;
+    if value.is_empty() {
+        return Ok((name, None));
+    }
+    let value = HeaderValue::from_str(value).map_err(|err| err.to_string())?;
+    Ok((name, Some(value)))
+}
+
+/// Apply the matching rules to the response, later rules win over earlier ones
+pub async fn apply_header_rules<B>(
+    req: Request<B>,
+    next: Next<B>,
+    rules: Arc<HeaderRules>,
+) -> Response {
+    let path = req.uri().path().to_string();
+    let mut response = next.run(req).await;
+    for (name, value) in rules.headers_for(&path) {
+        match value {
+            Some(value) => response.headers_mut().insert(name.clone(), value.clone()),
+            None => response.headers_mut().remove(name),
+        };
+    }
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rules_apply_in_order_under_their_prefix() {
+        let rule = |path_prefix: &str, headers: &[(&str, &str)]| HeaderRule {
+            path_prefix: path_prefix.to_string(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| parse_header(name, value).unwrap())
+                .collect(),
+        };
+        let rules = HeaderRules {
+            rules: vec![
+                rule("/", &[("cross-origin-opener-policy", "same-origin")]),
+                rule("/embed/", &[("cross-origin-opener-policy", "")]),
+            ],
+        };
+        let values = |path| {
+            rules
+                .headers_for(path)
+                .map(|(_, value)| value.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            values("/index.html"),
+            vec![Some(HeaderValue::from_static("same-origin"))]
+        );
+        assert_eq!(values("/embed/map.html").last(), Some(&None));
+        assert!(parse_header("bad header", "value").is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use axum::{
+    http::{header::HOST, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::CrateConfig;
+
+/// The hosts always allowed, so the page keeps working from this machine
+const LOCAL_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "::1"];
+
+/// The `web.serve.allowed_hosts`, against DNS rebinding when the server is reachable from
+/// other machines
+#[derive(Debug, Default)]
+pub struct AllowedHosts {
+    /// Lowercase host names without ports, unset if every host is allowed
+    hosts: Option<Vec<String>>,
+}
+
+impl AllowedHosts {
+    pub fn from_config(config: &CrateConfig) -> Self {
+        let hosts = config
+            .dioxus_config
+            .web
+            .serve
+            .allowed_hosts
+            .as_ref()
+            .map(|hosts| {
+                hosts
+                    .iter()
+                    .map(|host| host.to_lowercase())
+                    .chain(LOCAL_HOSTS.iter().map(|host| host.to_string()))
+                    .collect()
+            });
+        Self { hosts }
+    }
+
+    /// Whether a request for the host, optionally with a port, is allowed
+    fn allows(&self, host: Option<&str>) -> bool {
+        let Some(hosts) = &self.hosts else {
+            return true;
+        };
+        let Some(host) = host else {
+            return false;
+        };
+        let host = strip_port(host).to_lowercase();
+        hosts.iter().any(|allowed| *allowed == host)
+    }
+}
+
+/// The host name of a `Host` header, without the brackets of an IPv6 address
+fn strip_port(host: &str) -> &str {
+    if let Some(rest) = host.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest);
+    }
+    host.rsplit_once(':').map_or(host, |(name, _)| name)
+}
+
+/// Answer with 403 to requests for a host that isn't allowed
+pub async fn check_host<B>(req: Request<B>, next: Next<B>, hosts: Arc<AllowedHosts>) -> Response {
+    // HTTP/2 requests name the host in the uri instead of the header
+    let host = req
+        .uri()
+        .authority()
+        .map(|authority| authority.as_str())
+        .or_else(|| req.headers().get(HOST)?.to_str().ok());
+    if !hosts.allows(host) {
+        log::warn!(
+            "Rejected a request for {}, it isn't in web.serve.allowed_hosts",
+            host.unwrap_or("no host")
+        );
+        return (StatusCode::FORBIDDEN, "Host not allowed").into_response();
+    }
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn only_listed_and_local_hosts_are_allowed() {
+        let hosts = AllowedHosts {
+            hosts: Some(vec!["dev.example.com".to_string(), "localhost".to_string()]),
+        };
+        assert!(hosts.allows(Some("dev.example.com")));
+        assert!(hosts.allows(Some("Dev.Example.com:8080")));
+        assert!(hosts.allows(Some("localhost:8080")));
+        assert!(!hosts.allows(Some("evil.example.com")));
+        assert!(!hosts.allows(None));
+
+        let hosts = AllowedHosts {
+            hosts: Some(LOCAL_HOSTS.iter().map(|host| host.to_string()).collect()),
+        };
+        assert!(hosts.allows(Some("[::1]:8080")));
+        assert!(hosts.allows(Some("127.0.0.1:8080")));
+        assert!(!hosts.allows(Some("192.168.1.2:8080")));
+
+        assert!(AllowedHosts::default().allows(None));
+    }
+}
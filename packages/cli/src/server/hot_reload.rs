@@ -1,49 +1,93 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
 
 use axum::{
-    extract::{ws::Message, WebSocketUpgrade},
+    extract::{ws::Message, RawQuery, WebSocketUpgrade},
     response::IntoResponse,
     Extension, TypedHeader,
 };
 use dioxus_core::Template;
-use dioxus_html::HtmlCtx;
-use dioxus_rsx::hot_reload::FileMap;
+use flate2::{write::DeflateEncoder, Compression};
 use tokio::sync::broadcast;
 
-use super::BuildManager;
+use super::{BuildManager, CrateFileMaps};
 use crate::CrateConfig;
 
 pub struct HotReloadState {
     pub messages: broadcast::Sender<Template<'static>>,
     pub build_manager: Arc<BuildManager>,
-    pub file_map: Arc<Mutex<FileMap<HtmlCtx>>>,
+    pub file_map: Arc<Mutex<CrateFileMaps>>,
     pub watcher_config: CrateConfig,
 }
 
+impl HotReloadState {
+    /// A state without any rsx or file watcher, its templates only come from `messages`, so
+    /// tests can drive the hot reload websocket without changing a file
+    #[cfg(test)]
+    pub fn detached(config: &CrateConfig) -> Self {
+        let (reload_tx, _) = broadcast::channel(16);
+        Self {
+            messages: broadcast::channel(16).0,
+            build_manager: Arc::new(BuildManager::new(config, reload_tx)),
+            file_map: Arc::new(Mutex::new(CrateFileMaps::default())),
+            watcher_config: config.clone(),
+        }
+    }
+}
+
+/// The query of the pages that can inflate the templates, older ones only read text frames
+const DEFLATE_QUERY: &str = "compress=deflate";
+
+/// A template as the JSON text the pages parse, or deflated into a binary frame
+fn template_message(template: &Template<'static>, deflate: bool) -> Message {
+    let json = serde_json::to_string(template).unwrap();
+    if !deflate {
+        return Message::Text(json);
+    }
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+    match encoder
+        .write_all(json.as_bytes())
+        .and_then(|_| encoder.finish())
+    {
+        Ok(deflated) => Message::Binary(deflated),
+        Err(_) => Message::Text(json),
+    }
+}
+
 pub async fn hot_reload_handler(
     ws: WebSocketUpgrade,
     _: Option<TypedHeader<headers::UserAgent>>,
+    RawQuery(query): RawQuery,
     Extension(state): Extension<Arc<HotReloadState>>,
 ) -> impl IntoResponse {
+    // tungstenite, which axum's websockets are built on, can't negotiate permessage-deflate,
+    // so `web.serve.ws_compression` deflates the templates themselves
+    let deflate = state
+        .watcher_config
+        .dioxus_config
+        .web
+        .serve
+        .ws_compression
+        .unwrap_or(false)
+        && query.map_or(false, |query| {
+            query.split('&').any(|pair| pair == DEFLATE_QUERY)
+        });
+    let ws = ws.protocols([super::RELOAD_PROTOCOL]);
     ws.on_upgrade(|mut socket| async move {
         log::info!("🔥 Hot Reload WebSocket connected");
+        // Subscribe before taking the snapshot so that no update produced while the
+        // snapshot is being sent is missed. A template may be sent twice, which is harmless.
+        let mut rx = state.messages.subscribe();
         {
             // update any rsx calls that changed before the websocket connected.
             {
                 log::info!("🔮 Finding updates since last compile...");
-                let templates: Vec<_> = {
-                    state
-                        .file_map
-                        .lock()
-                        .unwrap()
-                        .map
-                        .values()
-                        .filter_map(|(_, template_slot)| *template_slot)
-                        .collect()
-                };
+                let templates = state.file_map.lock().unwrap().templates();
                 for template in templates {
                     if socket
-                        .send(Message::Text(serde_json::to_string(&template).unwrap()))
+                        .send(template_message(&template, deflate))
                         .await
                         .is_err()
                     {
@@ -54,17 +98,104 @@ pub async fn hot_reload_handler(
             log::info!("finished");
         }
 
-        let mut rx = state.messages.subscribe();
         loop {
             if let Ok(rsx) = rx.recv().await {
-                if socket
-                    .send(Message::Text(serde_json::to_string(&rsx).unwrap()))
-                    .await
-                    .is_err()
-                {
+                if socket.send(template_message(&rsx, deflate)).await.is_err() {
                     break;
                 };
             }
         }
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    use dioxus_core::TemplateNode;
+    use futures::StreamExt;
+
+    use crate::server::{
+        files::MemoryFiles,
+        setup_router,
+        status::{BuildState, BuildStatus},
+        WsReloadState,
+    };
+
+    /// Serve the hot reload websocket, connect to it with `query` and push a template through
+    /// it, returning the message the client got
+    async fn push_template(
+        configure: impl FnOnce(&mut CrateConfig),
+        query: &str,
+    ) -> tokio_tungstenite::tungstenite::Message {
+        let mut config = CrateConfig::new(None).unwrap();
+        configure(&mut config);
+        let state = Arc::new(HotReloadState::detached(&config));
+        let (reload_tx, _) = broadcast::channel(1);
+        let ws_reload = Arc::new(WsReloadState::new(reload_tx, &config));
+        let build_status = Arc::new(Mutex::new(BuildStatus::new(BuildState::Ok)));
+        let router = setup_router(
+            config,
+            Arc::new(MemoryFiles::default()),
+            ws_reload,
+            Some(state.clone()),
+            None,
+            build_status,
+            None,
+        )
+        .await
+        .unwrap();
+        let (addr, server_handle) = crate::server::spawn_router(router);
+
+        let url = format!("ws://{}/_dioxus/hot_reload{}", addr, query);
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        // The handler subscribes once the connection is upgraded
+        while state.messages.receiver_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let template = Template {
+            name: "src/main.rs:10:5:0",
+            roots: &[TemplateNode::Text { text: "hot" }],
+            node_paths: &[],
+            attr_paths: &[],
+        };
+        state.messages.send(template).unwrap();
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        server_handle.abort();
+        message
+    }
+
+    #[tokio::test]
+    async fn pushed_templates_reach_the_connected_client() {
+        let message = push_template(|_| {}, "").await;
+        let sent: serde_json::Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+        assert_eq!(sent["name"], "src/main.rs:10:5:0");
+    }
+
+    #[tokio::test]
+    async fn templates_are_deflated_for_the_pages_asking_for_it() {
+        let ws_compression =
+            |config: &mut CrateConfig| config.dioxus_config.web.serve.ws_compression = Some(true);
+
+        let message = push_template(ws_compression, "?compress=deflate").await;
+        let mut json = String::new();
+        std::io::Read::read_to_string(
+            &mut flate2::read::DeflateDecoder::new(&message.into_data()[..]),
+            &mut json,
+        )
+        .unwrap();
+        let sent: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(sent["name"], "src/main.rs:10:5:0");
+
+        // Older pages only read text frames
+        let message = push_template(ws_compression, "").await;
+        assert!(message.is_text());
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use crate::CrateConfig;
+
+/// The last page run through the transform, so regenerating an unchanged page doesn't run it
+/// again
+static CACHE: Mutex<Option<Transformed>> = Mutex::new(None);
+
+struct Transformed {
+    command: Vec<String>,
+    /// When the files among the arguments of the command were last changed, e.g. the script
+    modified: Vec<Option<SystemTime>>,
+    source: String,
+    output: String,
+}
+
+/// Run the dev page through the `web.serve.html_transform` command, which gets the page on
+/// stdin and prints the transformed page to stdout.
+///
+/// The page is returned unchanged without a configured command.
+pub fn transform_html(config: &CrateConfig, html: String) -> anyhow::Result<String> {
+    let Some(command) = config.dioxus_config.web.serve.html_transform.as_ref() else {
+        return Ok(html);
+    };
+    let Some((program, args)) = command.split_first() else {
+        return Ok(html);
+    };
+
+    let modified: Vec<_> = command
+        .iter()
+        .map(|arg| modified_time(config.crate_dir.join(arg)))
+        .collect();
+    let mut cache = CACHE.lock().unwrap();
+    if let Some(cached) = cache.as_ref() {
+        if &cached.command == command && cached.modified == modified && cached.source == html {
+            return Ok(cached.output.clone());
+        }
+    }
+
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(&config.crate_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+    // Written from another thread, so a command printing before it read the whole page
+    // can't block on a full stdout
+    let mut stdin = child.stdin.take().unwrap();
+    let input = html.clone();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    writer.join().unwrap()?;
+    if !output.status.success() {
+        anyhow::bail!("{} exited with {}", program, output.status);
+    }
+
+    let output = String::from_utf8(output.stdout)?;
+    *cache = Some(Transformed {
+        command: command.clone(),
+        modified,
+        source: html,
+        output: output.clone(),
+    });
+    Ok(output)
+}
+
+fn modified_time(path: PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
@@ -0,0 +1,113 @@
+use axum::{
+    body::{boxed, Bytes, StreamBody},
+    http::header::{CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE},
+    response::Response,
+};
+use hyper::body::HttpBody;
+
+const BODY_END: &[u8] = b"</body";
+
+/// Whether a response is html the reload client can be injected into, compressed bodies can't be
+pub fn is_html<B>(response: &hyper::Response<B>) -> bool {
+    let html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map_or(false, |content_type| content_type.starts_with("text/html"));
+    html && !response.headers().contains_key(CONTENT_ENCODING)
+}
+
+/// Relay the html body chunk by chunk as it arrives, with the reload client inserted before
+/// `</body>`, or at the end if the page has none
+pub fn inject_reload_client(response: hyper::Response<hyper::Body>, client: &str) -> Response {
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(CONTENT_LENGTH);
+    let injector = Injector::new(client);
+    let chunks = futures::stream::unfold(Some((body, injector)), |state| async move {
+        let (mut body, mut injector) = state?;
+        match body.data().await {
+            Some(Ok(chunk)) => Some((Ok(injector.push(&chunk)), Some((body, injector)))),
+            Some(Err(err)) => Some((Err(err), None)),
+            None => Some((Ok(injector.finish()), None)),
+        }
+    });
+    Response::from_parts(parts, boxed(StreamBody::new(chunks)))
+}
+
+/// Finds `</body` in the chunks of a page, which may be split between two of them
+struct Injector {
+    /// The client, taken once it is inserted
+    client: Option<Bytes>,
+    /// The end of the last chunk, held back in case it is the start of `</body`
+    carry: Vec<u8>,
+}
+
+impl Injector {
+    fn new(client: &str) -> Self {
+        Self {
+            client: Some(Bytes::from(client.to_string())),
+            carry: Vec::new(),
+        }
+    }
+
+    /// The bytes to send on for a chunk
+    fn push(&mut self, chunk: &[u8]) -> Bytes {
+        let Some(client) = &self.client else {
+            return Bytes::copy_from_slice(chunk);
+        };
+        let mut data = std::mem::take(&mut self.carry);
+        data.extend_from_slice(chunk);
+
+        if let Some(end) = find_ignore_case(&data, BODY_END) {
+            let mut out = Vec::with_capacity(data.len() + client.len());
+            out.extend_from_slice(&data[..end]);
+            out.extend_from_slice(client);
+            out.extend_from_slice(&data[end..]);
+            self.client = None;
+            return out.into();
+        }
+
+        let keep = data.len().min(BODY_END.len() - 1);
+        self.carry = data.split_off(data.len() - keep);
+        data.into()
+    }
+
+    /// The bytes held back and the client if the page had no `</body`
+    fn finish(&mut self) -> Bytes {
+        let mut out = std::mem::take(&mut self.carry);
+        if let Some(client) = self.client.take() {
+            out.extend_from_slice(&client);
+        }
+        out.into()
+    }
+}
+
+fn find_ignore_case(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn inject(chunks: &[&str]) -> String {
+        let mut injector = Injector::new("<script></script>");
+        let mut out = Vec::new();
+        for chunk in chunks {
+            out.extend_from_slice(&injector.push(chunk.as_bytes()));
+        }
+        out.extend_from_slice(&injector.finish());
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn client_is_inserted_before_the_body_end() {
+        let page = "<html><body>hi<script></script></body></html>";
+        assert_eq!(inject(&["<html><body>hi</body></html>"]), page);
+        assert_eq!(inject(&["<html><body>hi</bo", "dy></html>"]), page);
+        assert_eq!(inject(&["<html><body>", "hi", "<", "/body></html>"]), page);
+        assert_eq!(inject(&["<p>hi</p>"]), "<p>hi</p><script></script>");
+    }
+}
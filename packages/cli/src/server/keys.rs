@@ -0,0 +1,67 @@
+use std::{
+    io::BufRead,
+    sync::{atomic::Ordering, Arc},
+};
+
+use super::{schedule_rebuild, BuildManager};
+
+/// Read commands from the terminal while serving.
+///
+/// Each command is a single letter followed by enter, and applies to every served app:
+/// - `p` pauses or resumes the file watchers
+/// - `r` rebuilds now, even while paused
+/// - `o` opens the app in the browser again
+///
+/// Does nothing when stdin isn't a terminal, so piped or backgrounded runs are unaffected.
+pub fn listen(build_managers: Vec<Arc<BuildManager>>, watcher_ip: String, port: u16) {
+    let Some(first) = build_managers.first() else {
+        return;
+    };
+    if !atty::is(atty::Stream::Stdin) {
+        return;
+    }
+
+    log::info!("⌨️  Press p + enter to pause the watcher, r + enter to rebuild, o + enter to open the browser");
+
+    // The apps share the server, and with it the https setting
+    let scheme = match first.config.dioxus_config.web.https.enabled {
+        Some(true) => "https",
+        _ => "http",
+    };
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                return;
+            };
+            match line.trim() {
+                "p" => {
+                    let mut paused = false;
+                    for build_manager in &build_managers {
+                        paused = !build_manager.paused.fetch_xor(true, Ordering::SeqCst);
+                    }
+                    if paused {
+                        log::info!("⏸️  Watcher paused, press p + enter to resume");
+                    } else {
+                        log::info!("▶️  Watcher resumed");
+                    }
+                }
+                "r" => {
+                    for build_manager in &build_managers {
+                        schedule_rebuild(
+                            build_manager,
+                            &watcher_ip,
+                            port,
+                            &build_manager.config,
+                            vec![],
+                        );
+                    }
+                }
+                "o" => {
+                    _ = open::that(format!("{}://localhost:{}", scheme, port));
+                }
+                "" => {}
+                other => log::warn!("Unknown command {:?}, use p, r or o", other),
+            }
+        }
+    });
+}
@@ -0,0 +1,70 @@
+use std::fmt::Write;
+
+use axum::{
+    body::{boxed, BoxBody, Full},
+    http::{header::CONTENT_TYPE, Response},
+};
+
+use super::files::DirEntry;
+
+/// An html page linking to the entries of a directory of the output directory, for
+/// `web.serve.dir_listing`
+pub fn listing_response(path: &str, mut entries: Vec<DirEntry>) -> Response<BoxBody> {
+    // Directories first, like most file browsers
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+    let title = escape(path);
+    let mut items = String::new();
+    if path != "/" {
+        items.push_str("<li><a href=\"../\">../</a></li>");
+    }
+    for entry in &entries {
+        let name = escape(&entry.name);
+        let slash = if entry.is_dir { "/" } else { "" };
+        let _ = write!(
+            items,
+            "<li><a href=\"{name}{slash}\">{name}{slash}</a></li>"
+        );
+    }
+    let page = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Index of {title}</title></head>\
+         <body><h1>Index of {title}</h1><ul>{items}</ul></body></html>"
+    );
+    Response::builder()
+        .header(CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(boxed(Full::from(page)))
+        .unwrap()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn directories_are_listed_first() {
+        let entries = vec![
+            DirEntry {
+                name: "b<1>.js".to_string(),
+                is_dir: false,
+            },
+            DirEntry {
+                name: "assets".to_string(),
+                is_dir: true,
+            },
+        ];
+        let body = listing_response("/dist/", entries).into_body();
+        let page = hyper::body::to_bytes(body).await.unwrap();
+        let page = String::from_utf8(page.to_vec()).unwrap();
+        let assets = page.find("href=\"assets/\"").unwrap();
+        let script = page.find("href=\"b&lt;1&gt;.js\"").unwrap();
+        assert!(page.contains("href=\"../\""));
+        assert!(assets < script);
+    }
+}
@@ -0,0 +1,107 @@
+use std::path::{Path, PathBuf};
+
+use crate::CrateConfig;
+
+/// The `web.watcher.locale_paths` as absolute paths
+pub fn paths(config: &CrateConfig) -> Vec<PathBuf> {
+    config
+        .dioxus_config
+        .web
+        .watcher
+        .locale_paths
+        .iter()
+        .flatten()
+        .map(|path| config.crate_dir.join(path))
+        .collect()
+}
+
+/// Where a locale file is served from, at the same place relative to the output directory as
+/// to the crate directory
+fn dest(config: &CrateConfig, path: &Path) -> Option<PathBuf> {
+    let paths = paths(config);
+    if !paths.iter().any(|locales| path.starts_with(locales)) {
+        return None;
+    }
+    let relative = path.strip_prefix(&config.crate_dir).ok()?;
+    Some(config.out_dir.join(relative))
+}
+
+/// Copy every locale file into the output directory after a build
+pub fn copy_all(config: &CrateConfig) {
+    for locales in paths(config) {
+        let files = walkdir::WalkDir::new(&locales)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file());
+        for file in files {
+            if let Err(err) = sync(config, file.path()) {
+                log::error!("Failed to copy {}: {}", file.path().display(), err);
+            }
+        }
+    }
+}
+
+/// Copy the changed locale files into the output directory, or remove the deleted ones.
+///
+/// Returns false without doing anything unless every path is a locale file.
+pub fn sync_changed(config: &CrateConfig, changed: &[PathBuf]) -> bool {
+    if changed.is_empty() || !changed.iter().all(|path| dest(config, path).is_some()) {
+        return false;
+    }
+    for path in changed {
+        if let Err(err) = sync(config, path) {
+            log::error!("Failed to copy {}: {}", path.display(), err);
+        }
+    }
+    true
+}
+
+fn sync(config: &CrateConfig, path: &Path) -> std::io::Result<()> {
+    let Some(dest) = dest(config, path) else {
+        log::warn!(
+            "The locale file {} is outside the crate directory, it isn't served",
+            path.display()
+        );
+        return Ok(());
+    };
+    if path.is_file() {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(path, dest)?;
+    } else if !path.exists() {
+        if dest.is_dir() {
+            std::fs::remove_dir_all(dest)?;
+        } else if dest.is_file() {
+            std::fs::remove_file(dest)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn changed_and_deleted_locales_are_synced() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let mut config = CrateConfig::new(None).unwrap();
+        config.crate_dir = root.to_path_buf();
+        config.out_dir = root.join("dist");
+        config.dioxus_config.web.watcher.locale_paths = Some(vec![PathBuf::from("locales")]);
+        let en = root.join("locales/en/main.ftl");
+        std::fs::create_dir_all(en.parent().unwrap()).unwrap();
+        std::fs::write(&en, "hello = Hello").unwrap();
+
+        assert!(!sync_changed(&config, &[root.join("src/main.rs")]));
+        assert!(sync_changed(&config, &[en.clone()]));
+        let served = root.join("dist/locales/en/main.ftl");
+        assert_eq!(std::fs::read_to_string(&served).unwrap(), "hello = Hello");
+
+        std::fs::remove_file(&en).unwrap();
+        assert!(sync_changed(&config, &[en]));
+        assert!(!served.exists());
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CrateConfig, ServeError};
+
+/// The pid and port of the `dx serve` running for a crate
+#[derive(Debug, Serialize, Deserialize)]
+struct LockInfo {
+    pid: u32,
+    port: u16,
+}
+
+/// Marks a crate as being served, so a second `dx serve` doesn't fight over its rebuilds.
+///
+/// The lock file is removed when this is dropped. Exiting with ctrl-c skips destructors, so
+/// the shutdown handler calls [`release`] instead.
+pub struct ServeLock {
+    path: PathBuf,
+}
+
+impl ServeLock {
+    /// Take the lock of the crate, or fail if another `dx serve` that is still running holds it
+    pub fn acquire(config: &CrateConfig, port: u16) -> Result<Self, ServeError> {
+        let path = lock_path(config);
+
+        if let Some(existing) = read_lock(&path) {
+            if existing.pid != std::process::id() && is_running(existing.pid) {
+                return Err(ServeError::AlreadyServing {
+                    pid: existing.pid,
+                    port: existing.port,
+                });
+            }
+            log::debug!("Removing stale serve lock of pid {}", existing.pid);
+        }
+
+        let info = LockInfo {
+            pid: std::process::id(),
+            port,
+        };
+        // Failing to write the lock shouldn't stop serving
+        let written = path
+            .parent()
+            .map(std::fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| std::fs::write(&path, serde_json::to_string(&info).unwrap()));
+        if let Err(err) = written {
+            log::warn!("Failed to write {}: {}", path.display(), err);
+        }
+
+        Ok(Self { path })
+    }
+}
+
+impl Drop for ServeLock {
+    fn drop(&mut self) {
+        _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Remove the lock of the crate if it is held by this process
+pub fn release(config: &CrateConfig) {
+    let path = lock_path(config);
+    if read_lock(&path).map(|info| info.pid) == Some(std::process::id()) {
+        _ = std::fs::remove_file(path);
+    }
+}
+
+fn lock_path(config: &CrateConfig) -> PathBuf {
+    config.crate_dir.join(".dioxus").join("serve.lock")
+}
+
+fn read_lock(path: &Path) -> Option<LockInfo> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// A process of another user can't be signalled, but is running all the same
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stdout(Stdio::null())
+        .output()
+        .map(|output| {
+            output.status.success()
+                || String::from_utf8_lossy(&output.stderr).contains("Operation not permitted")
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(windows)]
+fn is_running(pid: u32) -> bool {
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .stderr(Stdio::null())
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn processes_of_other_users_are_running() {
+        // Signalling init fails with EPERM for anyone but root
+        assert!(is_running(1));
+        assert!(is_running(std::process::id()));
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use axum::{http::header::CONTENT_TYPE, response::IntoResponse};
+
+/// The upper bounds of the rebuild duration buckets, in seconds
+const DURATION_BUCKETS: [f64; 8] = [0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+/// Counters of the dev server, served in the Prometheus text format at `/_dioxus/metrics`
+struct Metrics {
+    rebuilds: AtomicU64,
+    build_failures: AtomicU64,
+    /// How many rebuilds took at most the duration of the bucket, not cumulative
+    duration_buckets: [AtomicU64; DURATION_BUCKETS.len()],
+    duration_millis: AtomicU64,
+    reload_clients: AtomicU64,
+    served_bytes: AtomicU64,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU64 = AtomicU64::new(0);
+
+static METRICS: Metrics = Metrics {
+    rebuilds: ZERO,
+    build_failures: ZERO,
+    duration_buckets: [ZERO; DURATION_BUCKETS.len()],
+    duration_millis: ZERO,
+    reload_clients: ZERO,
+    served_bytes: ZERO,
+};
+
+/// Count a finished rebuild and how long it took
+pub fn record_rebuild(succeeded: bool, elapsed: Duration) {
+    METRICS.rebuilds.fetch_add(1, Ordering::Relaxed);
+    if !succeeded {
+        METRICS.build_failures.fetch_add(1, Ordering::Relaxed);
+    }
+    METRICS
+        .duration_millis
+        .fetch_add(elapsed.as_millis() as u64, Ordering::Relaxed);
+    let seconds = elapsed.as_secs_f64();
+    if let Some(bucket) = DURATION_BUCKETS.iter().position(|le| seconds <= *le) {
+        METRICS.duration_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn reload_client_connected() {
+    METRICS.reload_clients.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn reload_client_disconnected() {
+    METRICS.reload_clients.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Count the bytes of a response of the file service
+pub fn record_served(bytes: u64) {
+    METRICS.served_bytes.fetch_add(bytes, Ordering::Relaxed);
+}
+
+pub async fn metrics_handler() -> impl IntoResponse {
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render(&METRICS),
+    )
+}
+
+fn render(metrics: &Metrics) -> String {
+    let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: u64| {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        let _ = writeln!(out, "{} {}", name, value);
+    };
+    metric(
+        "dioxus_rebuilds_total",
+        "counter",
+        "Rebuilds after the first build",
+        load(&metrics.rebuilds),
+    );
+    metric(
+        "dioxus_build_failures_total",
+        "counter",
+        "Rebuilds that failed",
+        load(&metrics.build_failures),
+    );
+    metric(
+        "dioxus_reload_clients",
+        "gauge",
+        "Pages connected to the reload socket",
+        load(&metrics.reload_clients),
+    );
+    metric(
+        "dioxus_served_bytes_total",
+        "counter",
+        "Bytes of the files served from the output directory",
+        load(&metrics.served_bytes),
+    );
+
+    let name = "dioxus_rebuild_duration_seconds";
+    let _ = writeln!(out, "# HELP {} How long rebuilds took", name);
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    let mut count = 0;
+    for (le, bucket) in DURATION_BUCKETS.iter().zip(&metrics.duration_buckets) {
+        count += load(bucket);
+        let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, le, count);
+    }
+    let total = load(&metrics.rebuilds);
+    let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, total);
+    let _ = writeln!(
+        out,
+        "{}_sum {}",
+        name,
+        load(&metrics.duration_millis) as f64 / 1000.0
+    );
+    let _ = writeln!(out, "{}_count {}", name, total);
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let metrics = Metrics {
+            rebuilds: AtomicU64::new(3),
+            build_failures: AtomicU64::new(1),
+            duration_buckets: [
+                AtomicU64::new(1),
+                ZERO,
+                AtomicU64::new(1),
+                ZERO,
+                ZERO,
+                ZERO,
+                ZERO,
+                ZERO,
+            ],
+            duration_millis: AtomicU64::new(200_500),
+            reload_clients: AtomicU64::new(2),
+            served_bytes: ZERO,
+        };
+        let out = render(&metrics);
+        assert!(out.contains("dioxus_rebuilds_total 3\n"));
+        assert!(out.contains("dioxus_reload_clients 2\n"));
+        assert!(out.contains("dioxus_rebuild_duration_seconds_bucket{le=\"1\"} 1\n"));
+        assert!(out.contains("dioxus_rebuild_duration_seconds_bucket{le=\"2.5\"} 2\n"));
+        assert!(out.contains("dioxus_rebuild_duration_seconds_bucket{le=\"120\"} 2\n"));
+        assert!(out.contains("dioxus_rebuild_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("dioxus_rebuild_duration_seconds_sum 200.5\n"));
+    }
+}
@@ -0,0 +1,186 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use axum::{
+    http::{header::CONTENT_TYPE, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::CrateConfig;
+
+/// Content types that `ServeDir` may guess differently than browsers and devtools expect
+fn content_type_for(path: &str) -> Option<&'static str> {
+    match Path::new(path).extension()?.to_str()? {
+        // Required for `WebAssembly.instantiateStreaming`
+        "wasm" => Some("application/wasm"),
+        // Source maps are JSON, devtools refuses to load them otherwise
+        "map" => Some("application/json"),
+        _ => None,
+    }
+}
+
+/// The content types served for each file extension, including `web.serve.mime_overrides`
+#[derive(Debug, Default)]
+pub struct MimeTypes {
+    overrides: HashMap<String, HeaderValue>,
+}
+
+impl MimeTypes {
+    /// Read the overrides from the config, skipping and warning about invalid ones
+    pub fn from_config(config: &CrateConfig) -> Self {
+        let mut overrides = HashMap::new();
+        for (extension, mime) in config
+            .dioxus_config
+            .web
+            .serve
+            .mime_overrides
+            .iter()
+            .flatten()
+        {
+            match HeaderValue::from_str(mime) {
+                Ok(value) if is_valid_mime(mime) => {
+                    overrides.insert(extension.trim_start_matches('.').to_lowercase(), value);
+                }
+                _ => log::warn!(
+                    "Ignoring invalid MIME type {:?} for .{} files in web.serve.mime_overrides",
+                    mime,
+                    extension.trim_start_matches('.')
+                ),
+            }
+        }
+        Self { overrides }
+    }
+
+    fn content_type_for(&self, path: &str) -> Option<HeaderValue> {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+        match self.overrides.get(&extension) {
+            Some(value) => Some(value.clone()),
+            None => content_type_for(path).map(HeaderValue::from_static),
+        }
+    }
+}
+
+/// Check for a `type/subtype` MIME type, optionally followed by parameters
+pub fn is_valid_mime(mime: &str) -> bool {
+    let essence = mime.split(';').next().unwrap_or_default().trim();
+    let is_token = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+    };
+    matches!(essence.split_once('/'), Some((kind, subtype)) if is_token(kind) && is_token(subtype))
+}
+
+/// Override the content type of successful responses for files the browser is picky about
+pub async fn set_content_type<B>(
+    req: Request<B>,
+    next: Next<B>,
+    mime_types: Arc<MimeTypes>,
+) -> Response {
+    let content_type = mime_types.content_type_for(req.uri().path());
+    let mut response = next.run(req).await;
+
+    if let Some(content_type) = content_type {
+        if response.status().is_success() {
+            response.headers_mut().insert(CONTENT_TYPE, content_type);
+        }
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use axum::{body::Body, http::StatusCode, middleware, routing::get_service, Router};
+    use tower_http::services::ServeDir;
+
+    fn mime_types() -> MimeTypes {
+        let mut overrides = HashMap::new();
+        overrides.insert("wgsl".to_string(), HeaderValue::from_static("text/wgsl"));
+        overrides.insert(
+            "glb".to_string(),
+            HeaderValue::from_static("model/gltf-binary"),
+        );
+        MimeTypes { overrides }
+    }
+
+    async fn fetch(path: &str) -> hyper::Response<hyper::Body> {
+        let dir = tempfile::tempdir().unwrap();
+        // Not a valid module, only the headers are checked
+        std::fs::write(dir.path().join("app_bg.wasm"), b"\0asm").unwrap();
+        std::fs::write(dir.path().join("shader.wgsl"), b"").unwrap();
+
+        let router = Router::new()
+            .fallback(get_service(ServeDir::new(dir.path())).handle_error(
+                |error: std::io::Error| async move {
+                    (StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+                },
+            ))
+            .layer(middleware::from_fn(
+                move |req: Request<Body>, next: Next<Body>| {
+                    set_content_type(req, next, Arc::new(mime_types()))
+                },
+            ));
+        let (addr, handle) = crate::server::spawn_router(router);
+
+        let resp = hyper::Client::new()
+            .get(format!("http://{}{}", addr, path).parse().unwrap())
+            .await
+            .unwrap();
+
+        // Read the body while the directory is still around
+        let (parts, body) = resp.into_parts();
+        let body = hyper::body::to_bytes(body).await.unwrap();
+        handle.abort();
+        hyper::Response::from_parts(parts, body.into())
+    }
+
+    #[tokio::test]
+    async fn wasm_is_served_as_application_wasm() {
+        let resp = fetch("/app_bg.wasm").await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[CONTENT_TYPE], "application/wasm");
+    }
+
+    #[tokio::test]
+    async fn overrides_are_applied() {
+        let resp = fetch("/shader.wgsl").await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[CONTENT_TYPE], "text/wgsl");
+    }
+
+    #[test]
+    fn overrides_ignore_case() {
+        let mime_types = mime_types();
+        assert!(mime_types.content_type_for("/model.gltf").is_none());
+        assert_eq!(
+            mime_types.content_type_for("/model.GLB").unwrap(),
+            "model/gltf-binary"
+        );
+    }
+
+    #[test]
+    fn validates_mime_types() {
+        assert!(is_valid_mime("text/plain"));
+        assert!(is_valid_mime("text/plain; charset=utf-8"));
+        assert!(is_valid_mime("application/vnd.api+json"));
+        assert!(!is_valid_mime("text"));
+        assert!(!is_valid_mime("/plain"));
+        assert!(!is_valid_mime("text/pl ain"));
+    }
+
+    #[tokio::test]
+    async fn missing_files_keep_their_content_type() {
+        let resp = fetch("/missing.wasm").await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_ne!(
+            resp.headers()
+                .get(CONTENT_TYPE)
+                .map(|value| value.as_bytes()),
+            Some(b"application/wasm".as_slice())
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    http::{header::CONTENT_TYPE, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use notify::{RecommendedWatcher, Watcher};
+
+use crate::{CrateConfig, DioxusConfig, MockConfig};
+
+/// The canned responses of `web.serve.mocks`, read again whenever `Dioxus.toml` changes so
+/// edited mocks apply without restarting
+pub struct Mocks {
+    mocks: Arc<Mutex<Vec<MockConfig>>>,
+    /// Reads the mocks again when the config file changes, for as long as they are served
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl Mocks {
+    pub fn from_config(config: &CrateConfig) -> Self {
+        let mocks = config
+            .dioxus_config
+            .web
+            .serve
+            .mocks
+            .clone()
+            .unwrap_or_default();
+        let mocks = Arc::new(Mutex::new(mocks));
+        Self {
+            _watcher: watch_config(config, mocks.clone()),
+            mocks,
+        }
+    }
+
+    fn find(&self, method: &Method, path: &str) -> Option<MockConfig> {
+        self.mocks
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|mock| matches(mock, method, path))
+            .cloned()
+    }
+}
+
+/// Watch the `Dioxus.toml` of the crate and replace the mocks with the ones it sets now
+fn watch_config(
+    config: &CrateConfig,
+    mocks: Arc<Mutex<Vec<MockConfig>>>,
+) -> Option<RecommendedWatcher> {
+    let config_file = crate::config::acquire_dioxus_toml(&config.crate_dir)?;
+    let crate_dir = config.crate_dir.clone();
+    let serve_profile = config.serve_profile.clone();
+    let watched_file = config_file.clone();
+    let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        let changed = (event.kind.is_modify() || event.kind.is_create())
+            && event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == watched_file.file_name());
+        if !changed {
+            return;
+        }
+        match read_mocks(&crate_dir, serve_profile.as_deref()) {
+            Ok(read) => {
+                log::info!("🎭 Reloaded web.serve.mocks");
+                *mocks.lock().unwrap() = read;
+            }
+            Err(err) => log::warn!(
+                "Keeping the previous mocks, {} is invalid: {}",
+                watched_file.display(),
+                err
+            ),
+        }
+    });
+
+    // The directory rather than the file, editors replace the file when saving it
+    let watched = watcher.and_then(|mut watcher| {
+        watcher
+            .watch(&config.crate_dir, notify::RecursiveMode::NonRecursive)
+            .map(|_| watcher)
+    });
+    match watched {
+        Ok(watcher) => Some(watcher),
+        Err(err) => {
+            log::warn!(
+                "error watching {}, edited mocks apply after a restart: {}",
+                config_file.display(),
+                err
+            );
+            None
+        }
+    }
+}
+
+/// The mocks `Dioxus.toml` sets, with the serve profile applied the way serve started with it
+fn read_mocks(crate_dir: &Path, serve_profile: Option<&str>) -> crate::Result<Vec<MockConfig>> {
+    let mut config = DioxusConfig::load_from(crate_dir)?.unwrap_or_default();
+    if let Some(profile) = serve_profile {
+        config.web.apply_serve_profile(profile)?;
+    }
+    Ok(config.web.serve.mocks.unwrap_or_default())
+}
+
+fn matches(mock: &MockConfig, method: &Method, path: &str) -> bool {
+    let mock_method = mock.method.as_deref().unwrap_or("GET");
+    mock_method.eq_ignore_ascii_case(method.as_str())
+        && mock.path.trim_end_matches('/') == path.trim_end_matches('/')
+}
+
+/// Answer requests matching a mock with its canned response, before the proxies and the
+/// file service see them
+pub async fn respond<B>(req: Request<B>, next: Next<B>, mocks: Arc<Mocks>) -> Response {
+    let Some(mock) = mocks.find(req.method(), req.uri().path()) else {
+        return next.run(req).await;
+    };
+
+    let status = mock
+        .status
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .unwrap_or(StatusCode::OK);
+    let content_type = mock
+        .content_type
+        .as_deref()
+        .and_then(|content_type| HeaderValue::from_str(content_type).ok())
+        .unwrap_or_else(|| HeaderValue::from_static("application/json"));
+    (
+        status,
+        [(CONTENT_TYPE, content_type)],
+        mock.body.unwrap_or_default(),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use axum::{body::Body, middleware, routing::any, Router};
+
+    fn mock(path: &str, method: Option<&str>, status: Option<u16>, body: &str) -> MockConfig {
+        MockConfig {
+            path: path.to_string(),
+            method: method.map(str::to_string),
+            status,
+            body: Some(body.to_string()),
+            content_type: None,
+        }
+    }
+
+    async fn fetch(method: Method, path: &str) -> hyper::Response<hyper::Body> {
+        let mocks = Arc::new(Mocks {
+            mocks: Arc::new(Mutex::new(vec![
+                mock("/api/user", None, None, r#"{"name":"ferris"}"#),
+                mock("/api/user", Some("post"), Some(201), ""),
+            ])),
+            _watcher: None,
+        });
+        let router = Router::new()
+            .route("/*path", any(|| async { "backend" }))
+            .layer(middleware::from_fn(
+                move |req: Request<Body>, next: Next<Body>| respond(req, next, mocks.clone()),
+            ));
+        let (addr, handle) = crate::server::spawn_router(router);
+
+        let req = Request::builder()
+            .method(method)
+            .uri(format!("http://{}{}", addr, path))
+            .body(hyper::Body::empty())
+            .unwrap();
+        let resp = hyper::Client::new().request(req).await.unwrap();
+        handle.abort();
+        resp
+    }
+
+    #[tokio::test]
+    async fn mocks_answer_matching_requests() {
+        let resp = fetch(Method::GET, "/api/user/").await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(
+            hyper::body::to_bytes(resp.into_body()).await.unwrap(),
+            r#"{"name":"ferris"}"#
+        );
+
+        let resp = fetch(Method::POST, "/api/user").await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
+    #[test]
+    fn reloaded_mocks_keep_the_serve_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = include_str!("../assets/dioxus.toml")
+            .replace("{{project-name}}", "app")
+            .replace("{{default-platform}}", "web");
+        let mocks = r#"
+[[web.serve.mocks]]
+path = "/api/user"
+body = "base"
+
+[web.serve.profiles.demo]
+mocks = [{ path = "/api/user", body = "demo" }]
+"#;
+        std::fs::write(dir.path().join("Dioxus.toml"), config + mocks).unwrap();
+
+        let body = |profile| read_mocks(dir.path(), profile).unwrap()[0].body.clone();
+        assert_eq!(body(None).as_deref(), Some("base"));
+        assert_eq!(body(Some("demo")).as_deref(), Some("demo"));
+    }
+
+    #[tokio::test]
+    async fn other_requests_pass_through() {
+        for (method, path) in [(Method::GET, "/api/users"), (Method::DELETE, "/api/user")] {
+            let resp = fetch(method, path).await;
+            assert_eq!(
+                hyper::body::to_bytes(resp.into_body()).await.unwrap(),
+                "backend"
+            );
+        }
+    }
+}
@@ -1,55 +1,323 @@
-use crate::{builder, serve::Serve, BuildResult, CrateConfig, Result};
+use crate::{
+    builder, serve::Serve, BuildResult, CompressionLevel, CrateConfig, ReloadStrategy, Result,
+    ServeError, SlowClientPolicy, WebWatcherConfig,
+};
 use axum::{
-    body::{Full, HttpBody},
+    body::{boxed, Body, BoxBody, Full},
     extract::{ws::Message, Extension, TypedHeader, WebSocketUpgrade},
     http::{
-        header::{HeaderName, HeaderValue},
-        Method, Response, StatusCode,
+        header::{HeaderName, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE},
+        Extensions, HeaderMap, Method, Request, Response, StatusCode, Version,
     },
-    response::IntoResponse,
+    middleware::{self, Next},
+    response::{Html, IntoResponse},
     routing::{get, get_service},
     Router,
 };
 use axum_server::tls_rustls::RustlsConfig;
 use cargo_metadata::diagnostic::Diagnostic;
 use dioxus_core::Template;
-use dioxus_html::HtmlCtx;
 use dioxus_rsx::hot_reload::*;
+use futures::future::BoxFuture;
 use notify::{RecommendedWatcher, Watcher};
 use std::{
-    net::UdpSocket,
-    path::PathBuf,
+    net::{SocketAddr, UdpSocket},
+    path::{Path, PathBuf},
     process::Command,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::{
+    broadcast::{self, Sender},
+    oneshot,
 };
-use tokio::sync::broadcast::{self, Sender};
 use tower::ServiceBuilder;
-use tower_http::services::fs::{ServeDir, ServeFileSystemResponseBody};
 use tower_http::{
+    compression::{
+        predicate::{DefaultPredicate, Predicate},
+        CompressionLayer,
+    },
     cors::{Any, CorsLayer},
+    timeout::TimeoutLayer,
     ServiceBuilderExt,
 };
 
 #[cfg(feature = "plugin")]
-use plugin::PluginManager;
+use crate::plugin::PluginManager;
+
+mod apps;
+pub use apps::{startup_apps, ServedApp};
+
+mod bell;
+
+mod build_command;
+
+mod bundle;
 
+mod check;
+pub use check::check_config;
+
+mod client_auth;
+
+mod sni;
+
+mod control;
+use control::ControlRoutes;
+
+mod crates;
+use crates::CrateFileMaps;
+
+mod lock;
+mod metrics;
+mod mime;
+mod mocks;
 mod proxy;
+pub use proxy::discover_backends;
+
+mod extra_files;
+
+mod files;
+use files::{DiskFiles, FileSource};
+
+mod gate;
+
+mod header_rules;
+
+mod hosts;
+
+mod inject;
 
 mod hot_reload;
 use hot_reload::*;
 
+mod html_transform;
+pub use html_transform::transform_html;
+
 mod output;
 use output::*;
 
+mod preview;
+
+mod probe;
+use probe::WatchProbe;
+
+mod cooldown;
+use cooldown::{CooldownDecision, RebuildCooldown};
+
+mod diffs;
+
+mod status;
+use status::{BuildState, BuildStatus, SharedBuildStatus};
+
+mod strategy;
+use strategy::StrategyMatrix;
+
+mod timing;
+
+mod triggers;
+use triggers::ReloadTrigger;
+
+mod keys;
+
+mod listing;
+
+mod locales;
+
+mod warmup;
+
+/// Messages sent to the reload client over `/_dioxus/ws`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadMessage {
+    /// Reload the whole page
+    Reload,
+    /// Re-fetch the page's stylesheets without reloading it
+    CssInject,
+    /// A rebuild is taking a while, show the building overlay
+    Building,
+    /// A build that showed the building overlay finished, hide the overlay
+    Built,
+}
+
+impl ReloadMessage {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ReloadMessage::Reload => "reload",
+            ReloadMessage::CssInject => "css",
+            ReloadMessage::Building => "building",
+            ReloadMessage::Built => "built",
+        }
+    }
+}
+
+/// Sent once the initial build succeeded and the server accepts connections.
+///
+/// With `web.serve.background_build` the initial build is still running when this is sent.
+#[derive(Debug, Clone)]
+pub struct ServeReady {
+    /// The url the app is served at
+    pub url: String,
+    /// Sends reloads and templates to the connected browsers
+    pub handle: ServeHandle,
+}
+
+/// Reloads the connected browsers without going through the file watcher, for tools that
+/// embed the dev server
+#[derive(Debug, Clone)]
+pub struct ServeHandle {
+    reload_txs: Vec<broadcast::Sender<ReloadMessage>>,
+    hot_reload_tx: Option<broadcast::Sender<Template<'static>>>,
+}
+
+impl ServeHandle {
+    fn new(
+        reload_txs: Vec<broadcast::Sender<ReloadMessage>>,
+        hot_reload_tx: Option<broadcast::Sender<Template<'static>>>,
+    ) -> Self {
+        Self {
+            reload_txs,
+            hot_reload_tx,
+        }
+    }
+
+    /// Reload every connected page
+    pub fn reload(&self) {
+        self.send(ReloadMessage::Reload);
+    }
+
+    /// Re-fetch the stylesheets of every connected page without reloading it
+    pub fn inject_css(&self) {
+        self.send(ReloadMessage::CssInject);
+    }
+
+    fn send(&self, msg: ReloadMessage) {
+        for reload_tx in &self.reload_txs {
+            // Nothing to do without connected pages
+            let _ = reload_tx.send(msg);
+        }
+    }
+
+    /// Hot reload a template in every connected page.
+    ///
+    /// Returns false if the server was started without hot reload.
+    pub fn push_template(&self, template: Template<'static>) -> bool {
+        match &self.hot_reload_tx {
+            Some(hot_reload_tx) => {
+                let _ = hot_reload_tx.send(template);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// The sender to notify once the server is ready, with the handle it is sent along with
+type ReadySignal = (oneshot::Sender<ServeReady>, ServeHandle);
+
 pub struct BuildManager {
     config: CrateConfig,
-    reload_tx: broadcast::Sender<()>,
+    reload_tx: broadcast::Sender<ReloadMessage>,
+    /// Both watchers go through this guard, so a burst of events never starts overlapping
+    /// rebuilds
+    cooldown: Mutex<RebuildCooldown>,
+    /// Set while the watchers are paused from the keyboard
+    paused: AtomicBool,
+    status: SharedBuildStatus,
 }
 
 impl BuildManager {
+    fn new(config: &CrateConfig, reload_tx: broadcast::Sender<ReloadMessage>) -> Self {
+        let interval = config
+            .dioxus_config
+            .web
+            .watcher
+            .min_rebuild_interval_ms
+            .unwrap_or(0);
+        Self {
+            config: config.clone(),
+            reload_tx,
+            cooldown: Mutex::new(
+                RebuildCooldown::new(Duration::from_millis(interval)).with_settle(REBUILD_SETTLE),
+            ),
+            paused: AtomicBool::new(false),
+            status: Arc::new(Mutex::new(BuildStatus::new(BuildState::Ok))),
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
     fn rebuild(&self) -> Result<BuildResult> {
+        self.rebuild_and_reload(true)
+    }
+
+    /// Rebuild the project, reloading the pages afterwards only if `reload` is set
+    fn rebuild_and_reload(&self, reload: bool) -> Result<BuildResult> {
+        self.status.lock().unwrap().start();
+        let start = Instant::now();
+        // The pages aren't told about a build they won't be reloaded for
+        let result = match reload {
+            true => self.announce_building(|| self.run_rebuild(true)),
+            false => self.run_rebuild(false),
+        };
+        metrics::record_rebuild(result.is_ok(), start.elapsed());
+        self.status.lock().unwrap().finish(&result);
+        result
+    }
+
+    /// Tell the pages a build is running only once it took longer than `building_delay_ms`,
+    /// so quick rebuilds don't flash the building overlay
+    fn announce_building<T>(&self, build: impl FnOnce() -> Result<T>) -> Result<T> {
+        let delay = self
+            .config
+            .dioxus_config
+            .web
+            .serve
+            .building_delay_ms
+            .unwrap_or(DEFAULT_BUILDING_DELAY_MS);
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+        let reload_tx = self.reload_tx.clone();
+        let timer = std::thread::spawn(move || {
+            // Disconnected as soon as the build is done
+            let slow = done_rx.recv_timeout(Duration::from_millis(delay))
+                == Err(std::sync::mpsc::RecvTimeoutError::Timeout);
+            if slow {
+                let _ = reload_tx.send(ReloadMessage::Building);
+            }
+            slow
+        });
+
+        let result = build();
+        drop(done_tx);
+        let announced = timer.join().unwrap_or(false);
+        // Sent before the reload, as the pages that don't reload, like the unfocused ones with
+        // `reload_focused_only`, would keep showing the overlay
+        if announced {
+            let _ = self.reload_tx.send(ReloadMessage::Built);
+        }
+        result
+    }
+
+    fn run_rebuild(&self, reload: bool) -> Result<BuildResult> {
         log::info!("🪁 Rebuild project");
-        let result = builder::build(&self.config, true)?;
+        let clean = self
+            .config
+            .dioxus_config
+            .web
+            .serve
+            .clean_out_dir
+            .unwrap_or(false);
+        let mut result = match clean {
+            true => build_clean(&self.config)?,
+            false => {
+                // The output directory may have been removed while serving, e.g. by `cargo clean`
+                std::fs::create_dir_all(&self.config.out_dir)?;
+                build_app(&self.config, true)?
+            }
+        };
         // change the websocket reload state to true;
         // the page will auto-reload.
         if self
@@ -59,32 +327,116 @@ impl BuildManager {
             .watcher
             .reload_html
             .unwrap_or(false)
+            // A new version in the script urls for every build
+            || self.config.dioxus_config.web.serve.cache_bust.unwrap_or(false)
+            || !self
+                .config
+                .out_dir
+                .join(self.config.dioxus_config.web.serve.index_file())
+                .is_file()
         {
+            let start = Instant::now();
             let _ = Serve::regen_dev_page(&self.config);
+            result
+                .phases
+                .push(("dev page", start.elapsed().as_millis()));
+        }
+        let start = Instant::now();
+        if let Err(err) = gate::run_gate(&self.config) {
+            log::warn!("🚧 Reload suppressed, the gate command failed");
+            return Err(err);
         }
-        let _ = self.reload_tx.send(());
+        if self.config.dioxus_config.web.watcher.gate_command.is_some() {
+            result.phases.push(("gate", start.elapsed().as_millis()));
+        }
+        if !reload {
+            log::info!("🔕 Rebuilt for changes that don't affect the frontend, pages not reloaded");
+            return Ok(result);
+        }
+        // Every page connected to `/_dioxus/ws` holds a receiver
+        if self.reload_tx.receiver_count() == 0 {
+            log::info!(
+                "📭 Rebuild done but no page is connected, open the page to see the changes"
+            );
+        }
+        let _ = self.reload_tx.send(ReloadMessage::Reload);
         Ok(result)
     }
 }
 
 struct WsReloadState {
-    update: broadcast::Sender<()>,
+    update: broadcast::Sender<ReloadMessage>,
+    /// Let only the most recently focused page reload, see `web.serve.reload_focused_only`
+    focused_only: bool,
+    slow_client_policy: SlowClientPolicy,
+}
+
+impl WsReloadState {
+    fn new(update: broadcast::Sender<ReloadMessage>, config: &CrateConfig) -> Self {
+        Self {
+            update,
+            focused_only: config
+                .dioxus_config
+                .web
+                .serve
+                .reload_focused_only
+                .unwrap_or(false),
+            slow_client_policy: config
+                .dioxus_config
+                .web
+                .serve
+                .slow_client_policy
+                .unwrap_or(SlowClientPolicy::Drop),
+        }
+    }
+
+    /// The text sent to the pages for a message
+    fn text(&self, msg: ReloadMessage) -> &'static str {
+        match msg {
+            // The pages agree among themselves which one was focused last
+            ReloadMessage::Reload if self.focused_only => "reload-focused",
+            msg => msg.as_str(),
+        }
+    }
 }
 
-pub async fn startup(port: u16, config: CrateConfig, start_browser: bool) -> Result<()> {
+/// How long a message may take to send before a slow client is dropped
+const SLOW_CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_BUILDING_DELAY_MS: u64 = 300;
+
+/// Build and serve the crate until the process exits.
+///
+/// If `ready` is set it receives the url of the app and a [`ServeHandle`] once the first build
+/// is done and the server is listening.
+pub async fn startup(
+    port: u16,
+    config: CrateConfig,
+    start_browser: bool,
+    ready: Option<oneshot::Sender<ServeReady>>,
+) -> Result<()> {
+    // Refuse to fight another dx serve over the same crate
+    let _lock = lock::ServeLock::acquire(&config, port)?;
+
     // ctrl-c shutdown checker
-    let _crate_config = config.clone();
+    let crate_config = config.clone();
     let _ = ctrlc::set_handler(move || {
         #[cfg(feature = "plugin")]
-        let _ = PluginManager::on_serve_shutdown(&_crate_config);
+        if crate_config.plugins_enabled {
+            let _ = PluginManager::on_serve_shutdown(&crate_config);
+        }
+        lock::release(&crate_config);
+        reset_dashboard();
         std::process::exit(0);
     });
 
     let ip = get_ip().unwrap_or(String::from("0.0.0.0"));
 
-    match config.hot_reload {
-        true => serve_hot_reload(ip, port, config, start_browser).await?,
-        false => serve_default(ip, port, config, start_browser).await?,
+    if config.preview {
+        serve_preview(ip, port, config, start_browser, ready).await?;
+    } else if config.hot_reload {
+        serve_hot_reload(ip, port, config, start_browser, ready).await?;
+    } else {
+        serve_default(ip, port, config, start_browser, ready).await?;
     }
 
     Ok(())
@@ -96,182 +448,471 @@ pub async fn serve_default(
     port: u16,
     config: CrateConfig,
     start_browser: bool,
+    ready: Option<oneshot::Sender<ServeReady>>,
 ) -> Result<()> {
-    let first_build_result = crate::builder::build(&config, false)?;
-
+    let (app, first_build_result) = WatchedApp::watch_default(&ip, port, config).await?;
     log::info!("🚀 Starting development server...");
+    serve_app(&ip, port, app, first_build_result, start_browser, ready).await
+}
 
-    // WS Reload Watching
-    let (reload_tx, _) = broadcast::channel(100);
+/// Start the server in preview mode, rebuilding when the checked out git ref changes
+pub async fn serve_preview(
+    ip: String,
+    port: u16,
+    config: CrateConfig,
+    start_browser: bool,
+    ready: Option<oneshot::Sender<ServeReady>>,
+) -> Result<()> {
+    let (app, first_build_result) = WatchedApp::watch_preview(&ip, port, config)?;
+    log::info!("🚀 Starting preview server...");
+    serve_app(&ip, port, app, first_build_result, start_browser, ready).await
+}
 
-    // We got to own watcher so that it exists for the duration of serve
-    // Otherwise full reload won't work.
-    let _watcher = setup_file_watcher(&config, port, ip.clone(), reload_tx.clone()).await?;
+/// Start dx serve with hot reload
+pub async fn serve_hot_reload(
+    ip: String,
+    port: u16,
+    config: CrateConfig,
+    start_browser: bool,
+    ready: Option<oneshot::Sender<ServeReady>>,
+) -> Result<()> {
+    let (app, first_build_result) = WatchedApp::watch_hot_reload(&ip, port, config).await?;
+    log::info!("🚀 Starting development server...");
+    serve_app(&ip, port, app, first_build_result, start_browser, ready).await
+}
 
-    let ws_reload_state = Arc::new(WsReloadState {
-        update: reload_tx.clone(),
-    });
+/// Set up the router of a watched app and serve it, the same for every serve mode
+async fn serve_app(
+    ip: &String,
+    port: u16,
+    app: WatchedApp,
+    first_build_result: Option<BuildResult>,
+    start_browser: bool,
+    ready: Option<oneshot::Sender<ServeReady>>,
+) -> Result<()> {
+    let ready = ready.map(|ready| (ready, app.handle()));
+    keys::listen(vec![app.build_manager.clone()], ip.clone(), port);
 
     // HTTPS
     // Before console info so it can stop if mkcert isn't installed or fails
-    let rustls_config = get_rustls(&config).await?;
+    let rustls_config = get_rustls(&app.config).await?;
 
-    // Print serve info
-    print_console_info(
-        &ip,
-        port,
-        &config,
-        PrettierOptions {
-            changed: vec![],
-            warnings: first_build_result.warnings,
-            elapsed_time: first_build_result.elapsed_time,
-        },
-    );
+    // Print serve info, or build while the server is already running
+    let initial_build = finish_first_build(first_build_result, ip, port, &app.build_manager);
 
     // Router
-    let router = setup_router(config, ws_reload_state, None).await?;
+    let router = app
+        .router(initial_build.clone(), rustls_config.clone())
+        .await?;
 
     // Start server
-    start_server(port, router, start_browser, rustls_config).await?;
+    let start_browser = start_browser.then_some(initial_build);
+    start_server(
+        port,
+        router,
+        start_browser,
+        rustls_config,
+        &app.config,
+        ready,
+    )
+    .await?;
 
     Ok(())
 }
 
-/// Start dx serve with hot reload
-pub async fn serve_hot_reload(
-    ip: String,
-    port: u16,
+/// An app that is built and watched, waiting for its router to be served
+struct WatchedApp {
     config: CrateConfig,
-    start_browser: bool,
-) -> Result<()> {
-    let first_build_result = crate::builder::build(&config, false)?;
+    build_manager: Arc<BuildManager>,
+    hot_reload: Option<Arc<HotReloadState>>,
+    /// We got to own the watcher so that it exists for the duration of serve, otherwise
+    /// the reloads won't work. Preview mode polls git instead.
+    _watcher: Option<RecommendedWatcher>,
+}
 
-    log::info!("🚀 Starting development server...");
+impl WatchedApp {
+    /// Build and watch the app the way its config asks for, with `--preview` or `--hot-reload`
+    async fn watch(
+        ip: &String,
+        port: u16,
+        config: CrateConfig,
+    ) -> Result<(Self, Option<BuildResult>)> {
+        if config.preview {
+            Self::watch_preview(ip, port, config)
+        } else if config.hot_reload {
+            Self::watch_hot_reload(ip, port, config).await
+        } else {
+            Self::watch_default(ip, port, config).await
+        }
+    }
 
-    // Setup hot reload
-    let (reload_tx, _) = broadcast::channel(100);
-    let FileMapBuildResult { map, errors } =
-        FileMap::<HtmlCtx>::create(config.crate_dir.clone()).unwrap();
+    /// Build the app and rebuild it whenever a watched file changes.
+    ///
+    /// Returns the result of the first build, unless `web.serve.background_build` defers it.
+    async fn watch_default(
+        ip: &String,
+        port: u16,
+        config: CrateConfig,
+    ) -> Result<(Self, Option<BuildResult>)> {
+        let first_build_result = build_first(&config)?;
+
+        // WS Reload Watching
+        let (reload_tx, _) = broadcast::channel(100);
+        let build_manager = Arc::new(BuildManager::new(&config, reload_tx));
+        let watcher = setup_file_watcher(&config, port, ip.clone(), build_manager.clone()).await?;
+
+        let app = Self {
+            config,
+            build_manager,
+            hot_reload: None,
+            _watcher: Some(watcher),
+        };
+        Ok((app, first_build_result))
+    }
 
-    for err in errors {
-        log::error!("{}", err);
+    /// Build the app and rebuild it when the checked out git ref changes.
+    ///
+    /// Returns the result of the first build, unless `web.serve.background_build` defers it.
+    fn watch_preview(
+        ip: &String,
+        port: u16,
+        config: CrateConfig,
+    ) -> Result<(Self, Option<BuildResult>)> {
+        let first_build_result = build_first(&config)?;
+
+        // WS Reload Watching
+        let (reload_tx, _) = broadcast::channel(100);
+        let build_manager = Arc::new(BuildManager::new(&config, reload_tx));
+        preview::setup_git_watcher(&config, port, ip.clone(), build_manager.clone())?;
+
+        let app = Self {
+            config,
+            build_manager,
+            hot_reload: None,
+            _watcher: None,
+        };
+        Ok((app, first_build_result))
     }
 
-    let file_map = Arc::new(Mutex::new(map));
-    let build_manager = Arc::new(BuildManager {
-        config: config.clone(),
-        reload_tx: reload_tx.clone(),
-    });
+    /// Build the app, hot reload the rsx of changed files and rebuild for any other change.
+    ///
+    /// Returns the result of the first build, unless `web.serve.background_build` defers it.
+    async fn watch_hot_reload(
+        ip: &String,
+        port: u16,
+        config: CrateConfig,
+    ) -> Result<(Self, Option<BuildResult>)> {
+        let first_build_result = build_first(&config)?;
+
+        // Setup hot reload
+        let (reload_tx, _) = broadcast::channel(100);
+        let file_map = Arc::new(Mutex::new(CrateFileMaps::create(&config)));
+        let build_manager = Arc::new(BuildManager::new(&config, reload_tx));
+
+        let hot_reload_tx = broadcast::channel(100).0;
+
+        // States
+        let hot_reload_state = Arc::new(HotReloadState {
+            messages: hot_reload_tx.clone(),
+            build_manager: build_manager.clone(),
+            file_map: file_map.clone(),
+            watcher_config: config.clone(),
+        });
 
-    let hot_reload_tx = broadcast::channel(100).0;
+        // Setup file watcher
+        let watcher = setup_file_watcher_hot_reload(
+            &config,
+            port,
+            ip.clone(),
+            hot_reload_tx,
+            file_map,
+            build_manager.clone(),
+        )
+        .await?;
+
+        let app = Self {
+            config,
+            build_manager,
+            hot_reload: Some(hot_reload_state),
+            _watcher: Some(watcher),
+        };
+        Ok((app, first_build_result))
+    }
 
-    // States
-    let hot_reload_state = Arc::new(HotReloadState {
-        messages: hot_reload_tx.clone(),
-        build_manager: build_manager.clone(),
-        file_map: file_map.clone(),
-        watcher_config: config.clone(),
-    });
+    /// The router serving the output directory of the app and its reload sockets
+    async fn router(
+        &self,
+        initial_build: Option<Arc<AtomicBool>>,
+        rustls: Option<RustlsConfig>,
+    ) -> Result<Router> {
+        let reload_tx = self.build_manager.reload_tx.clone();
+        let ws_reload_state = Arc::new(WsReloadState::new(reload_tx, &self.config));
+        setup_router(
+            self.config.clone(),
+            DiskFiles::shared(&self.config),
+            ws_reload_state,
+            self.hot_reload.clone(),
+            initial_build,
+            self.build_manager.status.clone(),
+            rustls,
+        )
+        .await
+    }
 
-    let ws_reload_state = Arc::new(WsReloadState {
-        update: reload_tx.clone(),
-    });
+    /// Reloads the pages of this app
+    fn handle(&self) -> ServeHandle {
+        ServeHandle::new(
+            vec![self.build_manager.reload_tx.clone()],
+            self.hot_reload.as_ref().map(|state| state.messages.clone()),
+        )
+    }
+}
 
-    // Setup file watcher
-    // We got to own watcher so that it exists for the duration of serve
-    // Otherwise hot reload won't work.
-    let _watcher = setup_file_watcher_hot_reload(
-        &config,
-        port,
-        ip.clone(),
-        hot_reload_tx,
-        file_map,
-        build_manager,
-    )
-    .await?;
+/// Build the app and copy the `web.serve.extra_files` into the output
+fn build_app(config: &CrateConfig, quiet: bool) -> Result<BuildResult> {
+    // Errors show up without waiting for the codegen of the wasm
+    let check_start = Instant::now();
+    let check_first = config.dioxus_config.web.serve.check_first.unwrap_or(false);
+    if check_first {
+        builder::check(config)?;
+    }
+    let check_millis = check_start.elapsed().as_millis();
 
-    // HTTPS
-    // Before console info so it can stop if mkcert isn't installed or fails
-    let rustls_config = get_rustls(&config).await?;
+    let mut result = match &config.dioxus_config.web.serve.build_command {
+        Some(command) => build_command::run_build_command(config, command)?,
+        None => builder::build(config, quiet)?,
+    };
+    if check_first {
+        result.phases.insert(0, ("cargo check", check_millis));
+    }
+    let start = Instant::now();
+    extra_files::copy_all(config);
+    locales::copy_all(config);
+    result
+        .phases
+        .push(("extra files", start.elapsed().as_millis()));
+    Ok(result)
+}
 
-    // Print serve info
-    print_console_info(
-        &ip,
-        port,
-        &config,
-        PrettierOptions {
-            changed: vec![],
-            warnings: first_build_result.warnings,
-            elapsed_time: first_build_result.elapsed_time,
-        },
-    );
+/// Build into a staging directory next to the output directory and swap it in once the build
+/// succeeded, so files the build doesn't produce anymore stop being served.
+///
+/// The previous build is served until then, and kept if the build fails.
+fn build_clean(config: &CrateConfig) -> Result<BuildResult> {
+    if let Some(problem) = clean_out_dir_conflict(config) {
+        log::warn!("{}, rebuilding without cleaning it", problem);
+        std::fs::create_dir_all(&config.out_dir)?;
+        return build_app(config, true);
+    }
 
-    // Router
-    let router = setup_router(config, ws_reload_state, Some(hot_reload_state)).await?;
+    let staging = out_dir_sibling(&config.out_dir, "staging");
+    let _ = std::fs::remove_dir_all(&staging);
+    std::fs::create_dir_all(&staging)?;
+    let mut staged = config.clone();
+    staged.out_dir = staging.clone();
+    let result = build_app(&staged, true);
+    match &result {
+        Ok(_) => swap_out_dir(&config.out_dir, &staging),
+        Err(_) => {
+            let _ = std::fs::remove_dir_all(&staging);
+        }
+    }
+    result
+}
 
-    // Start server
-    start_server(port, router, start_browser, rustls_config).await?;
+/// Why the output directory can't be cleaned, if it holds the crate or its assets, e.g. with
+/// `out_dir = "."`
+fn clean_out_dir_conflict(config: &CrateConfig) -> Option<String> {
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let out_dir = canonical(&config.crate_dir.join(&config.out_dir));
+    for (name, dir) in [("crate", &config.crate_dir), ("asset", &config.asset_dir)] {
+        if canonical(dir).starts_with(&out_dir) {
+            return Some(format!(
+                "web.serve.clean_out_dir is ignored, the output directory {} contains the {} directory",
+                out_dir.display(),
+                name
+            ));
+        }
+    }
+    None
+}
 
-    Ok(())
+/// A hidden directory next to the output directory, e.g. `.dist-staging` for `dist`
+fn out_dir_sibling(out_dir: &Path, suffix: &str) -> PathBuf {
+    let name = out_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    out_dir.with_file_name(format!(".{}-{}", name, suffix))
+}
+
+/// Replace the output directory with the staged build.
+///
+/// Where the output directory can't be moved, e.g. while a file in it is served on windows,
+/// the staged build is copied over it instead and the stale files stay.
+fn swap_out_dir(out_dir: &Path, staging: &Path) {
+    let stale = out_dir_sibling(out_dir, "stale");
+    let _ = std::fs::remove_dir_all(&stale);
+    let moved = match out_dir.exists() {
+        true => std::fs::rename(out_dir, &stale),
+        false => Ok(()),
+    };
+    let swapped = moved.and_then(|_| std::fs::rename(staging, out_dir));
+    match swapped {
+        Ok(()) => {
+            let _ = std::fs::remove_dir_all(&stale);
+            return;
+        }
+        Err(err) => log::debug!("Failed to swap in {}: {}", staging.display(), err),
+    }
+
+    if !out_dir.exists() && stale.exists() {
+        let _ = std::fs::rename(&stale, out_dir);
+    }
+    let copy_options = fs_extra::dir::CopyOptions {
+        overwrite: true,
+        skip_exist: false,
+        buffer_size: 64000,
+        copy_inside: false,
+        content_only: true,
+        depth: 0,
+    };
+    if let Err(err) = std::fs::create_dir_all(out_dir)
+        .map_err(fs_extra::error::Error::from)
+        .and_then(|_| fs_extra::dir::copy(staging, out_dir, &copy_options))
+    {
+        log::error!(
+            "Failed to copy the build into {}: {}",
+            out_dir.display(),
+            err
+        );
+    }
+    let _ = std::fs::remove_dir_all(staging);
+}
+
+/// Run the first build now, unless `web.serve.background_build` defers it until the server
+/// is listening
+fn build_first(config: &CrateConfig) -> Result<Option<BuildResult>> {
+    let background = config
+        .dioxus_config
+        .web
+        .serve
+        .background_build
+        .unwrap_or(false);
+    if background && !config.fail_on_build_error {
+        return Ok(None);
+    }
+    if background {
+        log::info!("The initial build runs before serving, as --fail-on-build-error is set");
+    }
+    let result = build_app(config, false);
+    if background && result.is_err() {
+        log::error!("The initial build failed, not serving because of --fail-on-build-error");
+    }
+    result.map(Some)
 }
 
+/// Print the result of the first build, or start it in the background if it was deferred.
+///
+/// Returns a flag that is set once a deferred build is done.
+fn finish_first_build(
+    first_build_result: Option<BuildResult>,
+    ip: &String,
+    port: u16,
+    build_manager: &Arc<BuildManager>,
+) -> Option<Arc<AtomicBool>> {
+    if let Some(first_build_result) = first_build_result {
+        print_console_info(
+            ip,
+            port,
+            &build_manager.config,
+            PrettierOptions {
+                changed: vec![],
+                warnings: first_build_result.warnings,
+                elapsed_time: first_build_result.elapsed_time,
+                phases: first_build_result.phases,
+            },
+        );
+        return None;
+    }
+
+    let done = Arc::new(AtomicBool::new(false));
+    let build_done = done.clone();
+    let ip = ip.clone();
+    let build_manager = build_manager.clone();
+    build_manager.status.lock().unwrap().start();
+    // The watchers queue their rebuilds until this one is done
+    build_manager.cooldown.lock().unwrap().start();
+    tokio::task::spawn_blocking(move || {
+        let result = build_app(&build_manager.config, false);
+        build_manager.status.lock().unwrap().finish(&result);
+        let built = result.is_ok();
+        match result {
+            Ok(res) => print_console_info(
+                &ip,
+                port,
+                &build_manager.config,
+                PrettierOptions {
+                    changed: vec![],
+                    warnings: res.warnings,
+                    elapsed_time: res.elapsed_time,
+                    phases: res.phases,
+                },
+            ),
+            // The watcher rebuilds once the error is fixed
+            Err(err) => log::error!("{}", err),
+        }
+        build_done.store(true, Ordering::SeqCst);
+        // A failed build leaves nothing new to show
+        if built {
+            let _ = build_manager.reload_tx.send(ReloadMessage::Reload);
+        }
+
+        let delay = build_manager
+            .cooldown
+            .lock()
+            .unwrap()
+            .finish(Instant::now());
+        if let Some(delay) = delay {
+            std::thread::sleep(delay);
+            let config = build_manager.config.clone();
+            schedule_pending_rebuild(build_manager, ip, port, config);
+        }
+    });
+    Some(done)
+}
+
+/// Served while the output directory is missing, it refreshes itself until the next build lands
+const BUILDING_PAGE: &str = include_str!("../assets/building.html");
+
 const DEFAULT_KEY_PATH: &str = "ssl/key.pem";
 const DEFAULT_CERT_PATH: &str = "ssl/cert.pem";
 
 /// Returns an enum of rustls config and a bool if mkcert isn't installed
-async fn get_rustls(config: &CrateConfig) -> Result<Option<RustlsConfig>> {
+async fn get_rustls(config: &CrateConfig) -> Result<Option<RustlsConfig>, ServeError> {
     let web_config = &config.dioxus_config.web.https;
     if web_config.enabled != Some(true) {
         return Ok(None);
     }
 
-    let (cert_path, key_path) = match web_config.mkcert {
-        // mkcert, use it
-        Some(true) => {
-            // Get paths to store certs, otherwise use ssl/item.pem
-            let key_path = web_config
-                .key_path
-                .clone()
-                .unwrap_or(DEFAULT_KEY_PATH.to_string());
-
-            let cert_path = web_config
+    let certificates = web_config.certificates.as_deref().unwrap_or_default();
+    if !certificates.is_empty() {
+        // The certificate and key of mkcert or the paths serve the hosts without their own
+        let default = match web_config.mkcert {
+            Some(true) => Some(regenerate_certificates(config)?),
+            _ => web_config
                 .cert_path
                 .clone()
-                .unwrap_or(DEFAULT_CERT_PATH.to_string());
-
-            // Create ssl directory if using defaults
-            if key_path == DEFAULT_KEY_PATH && cert_path == DEFAULT_CERT_PATH {
-                _ = fs::create_dir("ssl");
-            }
-
-            let cmd = Command::new("mkcert")
-                .args([
-                    "-install",
-                    "-key-file",
-                    &key_path,
-                    "-cert-file",
-                    &cert_path,
-                    "localhost",
-                    "::1",
-                    "127.0.0.1",
-                ])
-                .spawn();
-
-            match cmd {
-                Err(e) => {
-                    match e.kind() {
-                        io::ErrorKind::NotFound => log::error!("mkcert is not installed. See https://github.com/FiloSottile/mkcert#installation for installation instructions."),
-                        e => log::error!("an error occured while generating mkcert certificates: {}", e.to_string()),
-                    };
-                    return Err("failed to generate mkcert certificates".into());
-                }
-                Ok(mut cmd) => {
-                    cmd.wait()?;
-                }
-            }
+                .zip(web_config.key_path.clone()),
+        };
+        let server_config = sni::sni_config(certificates, default, web_config.client_ca.as_deref())
+            .map_err(ServeError::InvalidCertificate)?;
+        return Ok(Some(RustlsConfig::from_config(Arc::new(server_config))));
+    }
 
-            (cert_path, key_path)
-        }
+    let (cert_path, key_path) = match web_config.mkcert {
+        // mkcert, use it
+        Some(true) => regenerate_certificates(config)?,
         // not mkcert
         Some(false) => {
             // get paths to cert & key
@@ -281,23 +922,134 @@ async fn get_rustls(config: &CrateConfig) -> Result<Option<RustlsConfig>> {
                 (cert, key)
             } else {
                 // missing cert or key
-                return Err("https is enabled but cert or key path is missing".into());
+                return Err(ServeError::TlsConfigIncomplete);
             }
         }
         // other
         _ => return Ok(None),
     };
 
+    if let Some(client_ca) = &web_config.client_ca {
+        let server_config = client_auth::client_auth_config(&cert_path, &key_path, client_ca)
+            .map_err(ServeError::InvalidCertificate)?;
+        return Ok(Some(RustlsConfig::from_config(Arc::new(server_config))));
+    }
+
     Ok(Some(
-        RustlsConfig::from_pem_file(cert_path, key_path).await?,
+        RustlsConfig::from_pem_file(cert_path, key_path)
+            .await
+            .map_err(ServeError::InvalidCertificate)?,
     ))
 }
 
+/// Create the output directory if it's missing and serve it by its absolute path, which is
+/// logged, so a surprising `out_dir` shows up before the first request
+pub fn resolve_out_dir(config: &mut CrateConfig) -> Result<(), ServeError> {
+    let out_dir = config.crate_dir.join(&config.out_dir);
+    let unavailable = |source| ServeError::OutDirUnavailable {
+        path: out_dir.clone(),
+        source,
+    };
+    std::fs::create_dir_all(&out_dir).map_err(unavailable)?;
+    let out_dir = out_dir.canonicalize().map_err(unavailable)?;
+
+    let crate_dir = config
+        .crate_dir
+        .canonicalize()
+        .unwrap_or_else(|_| config.crate_dir.clone());
+    if !out_dir.starts_with(&crate_dir) {
+        log::warn!(
+            "The output directory {} is outside the crate directory {}",
+            out_dir.display(),
+            crate_dir.display()
+        );
+    }
+    log::info!("📂 Serving {}", out_dir.display());
+    config.out_dir = out_dir;
+    Ok(())
+}
+
+/// Generate fresh mkcert certificates at the configured paths, replacing any existing ones
+/// once mkcert succeeded.
+///
+/// Returns the paths of the certificate and the key.
+pub fn regenerate_certificates(config: &CrateConfig) -> Result<(String, String), ServeError> {
+    let web_config = &config.dioxus_config.web.https;
+    // The paths hold the user's own certificates otherwise
+    if web_config.mkcert != Some(true) {
+        return Err(ServeError::MkcertDisabled);
+    }
+
+    // Get paths to store certs, otherwise use ssl/item.pem
+    let key_path = web_config
+        .key_path
+        .clone()
+        .unwrap_or(DEFAULT_KEY_PATH.to_string());
+
+    let cert_path = web_config
+        .cert_path
+        .clone()
+        .unwrap_or(DEFAULT_CERT_PATH.to_string());
+
+    // Create ssl directory if using defaults
+    if key_path == DEFAULT_KEY_PATH && cert_path == DEFAULT_CERT_PATH {
+        _ = fs::create_dir("ssl");
+    }
+
+    // Generated next to the old ones, which are only replaced if mkcert succeeds
+    let new_key_path = format!("{}.new", key_path);
+    let new_cert_path = format!("{}.new", cert_path);
+
+    let mut cmd = Command::new("mkcert");
+    // Leave the trust store alone where the CA is already trusted or can't be installed, e.g. CI
+    if web_config.install_ca.unwrap_or(true) {
+        cmd.arg("-install");
+    }
+    let cmd = cmd
+        .args([
+            "-key-file",
+            &new_key_path,
+            "-cert-file",
+            &new_cert_path,
+            "localhost",
+            "::1",
+            "127.0.0.1",
+        ])
+        .spawn();
+
+    match cmd {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(ServeError::MkcertMissing);
+        }
+        Err(e) => return Err(ServeError::MkcertFailed(e)),
+        Ok(mut cmd) => {
+            let status = cmd.wait().map_err(ServeError::MkcertFailed)?;
+            if !status.success() {
+                _ = fs::remove_file(&new_key_path);
+                _ = fs::remove_file(&new_cert_path);
+                return Err(ServeError::MkcertFailed(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("mkcert exited with {}", status),
+                )));
+            }
+        }
+    }
+
+    fs::rename(&new_key_path, &key_path).map_err(ServeError::MkcertFailed)?;
+    fs::rename(&new_cert_path, &cert_path).map_err(ServeError::MkcertFailed)?;
+
+    Ok((cert_path, key_path))
+}
+
 /// Sets up and returns a router
 async fn setup_router(
     config: CrateConfig,
+    files: Arc<dyn FileSource>,
     ws_reload: Arc<WsReloadState>,
     hot_reload: Option<Arc<HotReloadState>>,
+    initial_build: Option<Arc<AtomicBool>>,
+    build_status: SharedBuildStatus,
+    rustls: Option<RustlsConfig>,
 ) -> Result<Router> {
     // Setup cors
     let cors = CorsLayer::new()
@@ -318,118 +1070,479 @@ async fn setup_router(
             HeaderValue::from_static("unsafe-none"),
         )
     };
-
-    // Create file service
-    let file_service_config = config.clone();
-    let file_service = ServiceBuilder::new()
+    // Everything the page loads from the output directory gets these, the index route and
+    // the favicon included
+    let cross_origin = ServiceBuilder::new()
         .override_response_header(
             HeaderName::from_static("cross-origin-embedder-policy"),
             coep,
         )
-        .override_response_header(HeaderName::from_static("cross-origin-opener-policy"), coop)
-        .and_then(
-            move |response: Response<ServeFileSystemResponseBody>| async move {
-                let response = if file_service_config
-                    .dioxus_config
-                    .web
-                    .watcher
-                    .index_on_404
-                    .unwrap_or(false)
-                    && response.status() == StatusCode::NOT_FOUND
-                {
-                    let body = Full::from(
-                        // TODO: Cache/memoize this.
-                        std::fs::read_to_string(
-                            file_service_config
-                                .crate_dir
-                                .join(file_service_config.out_dir)
-                                .join("index.html"),
-                        )
-                        .ok()
-                        .unwrap(),
-                    )
-                    .map_err(|err| match err {})
-                    .boxed();
-                    Response::builder()
-                        .status(StatusCode::OK)
-                        .body(body)
-                        .unwrap()
-                } else {
-                    response.map(|body| body.boxed())
-                };
-                Ok(response)
-            },
-        )
-        .service(ServeDir::new(config.crate_dir.join(&config.out_dir)));
+        .override_response_header(HeaderName::from_static("cross-origin-opener-policy"), coop);
 
-    // Setup websocket
-    let mut router = Router::new().route("/_dioxus/ws", get(ws_handler));
+    // Create file service
+    let interstitial = config.dioxus_config.web.serve.interstitial();
+    let file_service_config = config.clone();
+    let file_service_files = files.clone();
+    let served_files = files.clone();
+    let dir_listing = config.dioxus_config.web.serve.dir_listing.unwrap_or(false);
+    let file_service = cross_origin
+        .clone()
+        .and_then(move |response: Response<BoxBody>| async move {
+            if let Some(length) = response
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|length| length.to_str().ok()?.parse().ok())
+            {
+                metrics::record_served(length);
+            }
+            let building = || {
+                error_page(&file_service_config, StatusCode::SERVICE_UNAVAILABLE).unwrap_or_else(
+                    || {
+                        Response::builder()
+                            .status(StatusCode::SERVICE_UNAVAILABLE)
+                            .header(CONTENT_TYPE, "text/html")
+                            .body(boxed(Full::from(BUILDING_PAGE)))
+                            .unwrap()
+                    },
+                )
+            };
+
+            let still_building = initial_build
+                .as_ref()
+                .map_or(false, |done| !done.load(Ordering::SeqCst));
+
+            let response = if interstitial && (still_building || !file_service_files.available()) {
+                // The first build is still running, or the output directory is gone and
+                // will be recreated by the next rebuild
+                building()
+            } else if file_service_config
+                .dioxus_config
+                .web
+                .watcher
+                .index_on_404
+                .unwrap_or(false)
+                && response.status() == StatusCode::NOT_FOUND
+            {
+                // TODO: Cache/memoize this.
+                let index_file = file_service_config.dioxus_config.web.serve.index_file();
+                match file_service_files.read(index_file).await {
+                    Ok(index) => Response::builder()
+                        .status(StatusCode::OK)
+                        .body(boxed(Full::from(index)))
+                        .unwrap(),
+                    Err(_) if interstitial => building(),
+                    Err(_) => response,
+                }
+            } else {
+                response
+            };
+            let response = match response.status() {
+                StatusCode::NOT_FOUND => {
+                    error_page(&file_service_config, StatusCode::NOT_FOUND).unwrap_or(response)
+                }
+                _ => response,
+            };
+            Ok(response)
+        })
+        .service(tower::service_fn(move |req: Request<Body>| {
+            let served_files = served_files.clone();
+            async move {
+                let path = req.uri().path().to_string();
+                let response = served_files.serve(req).await?;
+                // Only directories without an index get here, `ServeDir` serves the others
+                if dir_listing
+                    && response.status() == StatusCode::NOT_FOUND
+                    && path.ends_with('/')
+                    && !path.split('/').any(|segment| segment == "..")
+                {
+                    if let Ok(entries) = served_files.list(path.trim_start_matches('/')).await {
+                        return Ok(listing::listing_response(&path, entries));
+                    }
+                }
+                Ok(response)
+            }
+        }));
+
+    let mime_types = Arc::new(mime::MimeTypes::from_config(&config));
+    let mocks = Arc::new(mocks::Mocks::from_config(&config));
+
+    let mut router = Router::new();
+
+    // The file service only looks for `index.html` in directories
+    let index_file = config.dioxus_config.web.serve.index_file();
+    if index_file != "index.html" {
+        let files = files.clone();
+        let index_file = index_file.to_string();
+        router = router.route(
+            "/",
+            get(move || async move {
+                match files.read(&index_file).await {
+                    Ok(index) => Html(String::from_utf8_lossy(&index).into_owned()).into_response(),
+                    Err(_) if interstitial => {
+                        (StatusCode::SERVICE_UNAVAILABLE, Html(BUILDING_PAGE)).into_response()
+                    }
+                    Err(_) => StatusCode::NOT_FOUND.into_response(),
+                }
+            })
+            .layer(cross_origin.clone()),
+        );
+    }
+
+    // Browsers ask for a favicon on their own, without one the 404 or the index fallback
+    // would show up as a broken icon in the console
+    router = router.route(
+        "/favicon.ico",
+        get(move || async move {
+            match files.read("favicon.ico").await {
+                Ok(icon) => ([(CONTENT_TYPE, "image/x-icon")], icon).into_response(),
+                Err(_) => StatusCode::NO_CONTENT.into_response(),
+            }
+        })
+        .layer(cross_origin),
+    );
 
     // Setup proxy
-    for proxy_config in config.dioxus_config.web.proxy.unwrap_or_default() {
-        router = proxy::add_proxy(router, &proxy_config)?;
+    let reload_client = crate::builder::reload_client(&config.dioxus_config);
+    let scheme = match config.dioxus_config.web.https.enabled {
+        Some(true) => "https",
+        _ => "http",
+    };
+    for proxy_config in config.dioxus_config.web.proxy.iter().flatten() {
+        router = proxy::add_proxy(router, proxy_config, &reload_client, scheme)?;
     }
 
     // Route file service
+    let error_config = config.clone();
     router = router.fallback(get_service(file_service).handle_error(
-        |error: std::io::Error| async move {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Unhandled internal error: {}", error),
-            )
+        move |error: std::io::Error| async move {
+            error_page(&error_config, StatusCode::INTERNAL_SERVER_ERROR).unwrap_or_else(|| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Unhandled internal error: {}", error),
+                )
+                    .into_response()
+            })
         },
     ));
+    router = compress(router, &config);
+
+    // Layered before the control routes are merged, the reload sockets are open for as long
+    // as the page is
+    if let Some(timeout) = config.dioxus_config.web.serve.request_timeout_ms {
+        router = router.layer(TimeoutLayer::new(Duration::from_millis(timeout)));
+    }
+
+    // Setup the websockets and status, only reachable from this machine if a control port is set
+    let mut control = ControlRoutes::default()
+        .socket(
+            "/_dioxus/ws",
+            "Websocket telling the page to reload after a rebuild",
+            get(ws_handler),
+        )
+        .socket(
+            "/_dioxus/hot_reload",
+            "Websocket sending the changed rsx templates",
+            get(hot_reload_handler),
+        )
+        .get(
+            "/_dioxus/status",
+            "The state of the most recent build as JSON",
+            get(status::status_handler),
+        )
+        .get(
+            "/_dioxus/metrics",
+            "Rebuild and request counters in the Prometheus text format",
+            get(metrics::metrics_handler),
+        );
+    if config
+        .dioxus_config
+        .web
+        .serve
+        .bundle_download
+        .unwrap_or(false)
+    {
+        let out_dir = config.crate_dir.join(&config.out_dir);
+        control = control.get(
+            "/_dioxus/bundle.zip",
+            "A zip of the output directory",
+            get(move || bundle::bundle_handler(out_dir.clone())),
+        );
+    }
+    let mut control = control
+        .into_router(|control| compress(control, &config))
+        .layer(Extension(ws_reload))
+        .layer(Extension(build_status));
+    if let Some(hot_reload) = hot_reload {
+        control = control.layer(Extension(hot_reload))
+    }
+    let allowed_hosts = Arc::new(hosts::AllowedHosts::from_config(&config));
+    match config.dioxus_config.web.serve.control_port {
+        Some(control_port) => {
+            // The loopback server is what DNS rebinding goes after
+            let allowed_hosts = allowed_hosts.clone();
+            let control = control.layer(cors.clone()).layer(middleware::from_fn(
+                move |req: Request<Body>, next: Next<Body>| {
+                    hosts::check_host(req, next, allowed_hosts.clone())
+                },
+            ));
+            start_control_server(control_port, control, rustls).await?
+        }
+        None => router = router.merge(control),
+    }
 
     // Setup routes
     router = router
-        .route("/_dioxus/hot_reload", get(hot_reload_handler))
-        .layer(cors)
-        .layer(Extension(ws_reload));
+        .layer(middleware::from_fn(
+            move |req: Request<Body>, next: Next<Body>| mocks::respond(req, next, mocks.clone()),
+        ))
+        .layer(middleware::from_fn(
+            move |req: Request<Body>, next: Next<Body>| {
+                mime::set_content_type(req, next, mime_types.clone())
+            },
+        ))
+        .layer(cors);
+
+    if config.dioxus_config.web.serve.header_rules.is_some() {
+        let rules = Arc::new(header_rules::HeaderRules::from_config(&config));
+        router = router.layer(middleware::from_fn(
+            move |req: Request<Body>, next: Next<Body>| {
+                header_rules::apply_header_rules(req, next, rules.clone())
+            },
+        ));
+    }
 
-    if let Some(hot_reload) = hot_reload {
-        router = router.layer(Extension(hot_reload))
+    if config
+        .dioxus_config
+        .web
+        .serve
+        .server_timing
+        .unwrap_or(false)
+    {
+        let routes = Arc::new(timing::TimedRoutes::from_config(&config));
+        router = router.layer(middleware::from_fn(
+            move |req: Request<Body>, next: Next<Body>| {
+                timing::add_server_timing(req, next, routes.clone())
+            },
+        ));
     }
 
+    router = router.layer(middleware::from_fn(
+        move |req: Request<Body>, next: Next<Body>| {
+            hosts::check_host(req, next, allowed_hosts.clone())
+        },
+    ));
+
     Ok(router)
 }
 
-/// Starts dx serve with no hot reload
+/// The `web.serve.error_pages` page of a status as a response with that status, if configured
+fn error_page(config: &CrateConfig, status: StatusCode) -> Option<Response<BoxBody>> {
+    let html = config
+        .dioxus_config
+        .web
+        .serve
+        .error_page(&config.crate_dir, status.as_u16())?;
+    Some(
+        Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, "text/html")
+            .body(boxed(Full::from(html)))
+            .unwrap(),
+    )
+}
+
+/// Compress the routes of the router with the configured `compression_level`, if any.
+///
+/// Only the files, the proxies and the plain http `/_dioxus/*` routes are compressed, the
+/// upgrades of the reload sockets have to reach the browser as they are.
+fn compress(router: Router, config: &CrateConfig) -> Router {
+    let Some(level) = config.dioxus_config.web.serve.compression_level else {
+        return router;
+    };
+    let quality = match level {
+        CompressionLevel::Fastest => tower_http::CompressionLevel::Fastest,
+        CompressionLevel::Default => tower_http::CompressionLevel::Default,
+        CompressionLevel::Best => tower_http::CompressionLevel::Best,
+    };
+    router.layer(
+        CompressionLayer::new()
+            .quality(quality)
+            .compress_when(DefaultPredicate::new().and(is_not_partial)),
+    )
+}
+
+/// Compressing a range of a file would break the byte offsets the client asked for, so
+/// partial responses are sent as they are
+fn is_not_partial(status: StatusCode, _: Version, _: &HeaderMap, _: &Extensions) -> bool {
+    status != StatusCode::PARTIAL_CONTENT
+}
+
+/// Serve the `/_dioxus/*` routes on the loopback interface, so other machines that can load
+/// the app can't connect to its reload sockets or read the build status
+async fn start_control_server(
+    port: u16,
+    router: Router,
+    rustls: Option<RustlsConfig>,
+) -> Result<(), ServeError> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let listener = bind_listener(addr).await?;
+    let scheme = if rustls.is_some() { "https" } else { "http" };
+
+    // Pages served over https can only open secure sockets
+    let server: BoxFuture<'static, Result<(), ServeError>> = match rustls {
+        Some(rustls) => {
+            let server = axum_server::tls_rustls::from_tcp_rustls(listener, rustls)
+                .serve(router.into_make_service());
+            Box::pin(async move {
+                server
+                    .await
+                    .map_err(|err| ServeError::ServerFailed(err.into()))
+            })
+        }
+        None => {
+            let server = axum::Server::from_tcp(listener)
+                .map_err(|err| ServeError::BindFailed {
+                    addr,
+                    source: err.into(),
+                })?
+                .serve(router.into_make_service());
+            Box::pin(async move {
+                server
+                    .await
+                    .map_err(|err| ServeError::ServerFailed(err.into()))
+            })
+        }
+    };
+
+    log::info!("🔒 Serving the dev control routes on {}://{}", scheme, addr);
+    tokio::spawn(async move {
+        if let Err(err) = server.await {
+            log::error!("The dev control server stopped: {}", err);
+        }
+    });
+    Ok(())
+}
+
+/// Starts dx serve with no hot reload.
+///
+/// With `start_browser` set the browser opens once the server listens and the initial build,
+/// if it is still running, is done.
 async fn start_server(
     port: u16,
     router: Router,
-    start_browser: bool,
+    start_browser: Option<Option<Arc<AtomicBool>>>,
     rustls: Option<RustlsConfig>,
-) -> Result<()> {
+    config: &CrateConfig,
+    ready: Option<ReadySignal>,
+) -> Result<(), ServeError> {
     // If plugins, call on_serve_start event
     #[cfg(feature = "plugin")]
-    PluginManager::on_serve_start(&config)?;
+    if config.plugins_enabled {
+        PluginManager::on_serve_start(config).map_err(ServeError::PluginFailed)?;
+    }
 
     // Parse address
-    let addr = format!("0.0.0.0:{}", port).parse().unwrap();
+    let addr: SocketAddr = format!("0.0.0.0:{}", port).parse().unwrap();
 
-    // Open the browser
-    if start_browser {
-        match rustls {
-            Some(_) => _ = open::that(format!("https://{}", addr)),
-            None => _ = open::that(format!("http://{}", addr)),
-        }
+    let scheme = if rustls.is_some() { "https" } else { "http" };
+
+    // Request the app once the server accepts connections
+    if config.dioxus_config.web.serve.warmup.unwrap_or(false) {
+        tokio::spawn(warmup::warmup(format!("{}://127.0.0.1:{}/", scheme, port)));
+    }
+
+    let listener = bind_listener(addr).await?;
+
+    // Only open the browser once the listener is bound, connections made before the server
+    // runs below wait in its backlog instead of being refused
+    if let Some(initial_build) = start_browser {
+        let delay = config.dioxus_config.web.serve.open_delay_ms.unwrap_or(0);
+        tokio::spawn(open_browser(
+            format!("{}://{}", scheme, addr),
+            initial_build,
+            Duration::from_millis(delay),
+        ));
+    }
+
+    if let Some((ready, handle)) = ready {
+        let url = format!("{}://localhost:{}/", scheme, port);
+        let _ = ready.send(ServeReady { url, handle });
     }
 
     // Start the server with or without rustls
     match rustls {
-        Some(rustls) => {
-            axum_server::bind_rustls(addr, rustls)
-                .serve(router.into_make_service())
-                .await?
+        Some(rustls) => axum_server::tls_rustls::from_tcp_rustls(listener, rustls)
+            .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+            .await
+            .map_err(|err| ServeError::ServerFailed(err.into())),
+        None => axum::Server::from_tcp(listener)
+            .map_err(|err| ServeError::BindFailed {
+                addr,
+                source: err.into(),
+            })?
+            .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+            .await
+            .map_err(|err| ServeError::ServerFailed(err.into())),
+    }
+}
+
+/// Open the app once the initial build is done, so the browser doesn't show the building page
+/// or an error first, and `web.serve.open_delay_ms` later
+async fn open_browser(url: String, initial_build: Option<Arc<AtomicBool>>, delay: Duration) {
+    if let Some(done) = initial_build {
+        while !done.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(100)).await;
         }
-        None => {
-            axum::Server::bind(&addr)
-                .serve(router.into_make_service())
-                .await?
+    }
+    tokio::time::sleep(delay).await;
+    _ = open::that(url);
+}
+
+/// How often to retry binding a port that is still in use
+const BIND_RETRIES: u32 = 5;
+const BIND_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Bind the listener of the server.
+///
+/// A port released by a server that just stopped can stay unavailable for a moment, so a port
+/// in use is retried a few times before giving up.
+async fn bind_listener(addr: SocketAddr) -> Result<std::net::TcpListener, ServeError> {
+    let mut attempt = 0;
+    loop {
+        match try_bind(addr) {
+            Ok(listener) => return Ok(listener),
+            Err(err) if err.kind() == io::ErrorKind::AddrInUse && attempt < BIND_RETRIES => {
+                attempt += 1;
+                // Jitter so two servers started together don't retry in lockstep
+                let jitter = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|now| now.subsec_millis() % 100)
+                    .unwrap_or(0);
+                log::debug!("Port {} is in use, retrying", addr.port());
+                tokio::time::sleep(BIND_RETRY_DELAY + Duration::from_millis(jitter.into())).await;
+            }
+            Err(err) => {
+                return Err(ServeError::BindFailed {
+                    addr,
+                    source: err.into(),
+                })
+            }
         }
     }
+}
 
-    Ok(())
+fn try_bind(addr: SocketAddr) -> io::Result<std::net::TcpListener> {
+    let socket = if addr.is_ipv4() {
+        tokio::net::TcpSocket::new_v4()?
+    } else {
+        tokio::net::TcpSocket::new_v6()?
+    };
+    // Lets the port be reused while the previous server's connections are in TIME_WAIT. On
+    // windows this would allow binding a port another server is listening on.
+    #[cfg(unix)]
+    socket.set_reuseaddr(true)?;
+    socket.bind(addr)?;
+    socket.listen(1024)?.into_std()
 }
 
 /// Sets up a file watcher
@@ -437,66 +1550,55 @@ async fn setup_file_watcher(
     config: &CrateConfig,
     port: u16,
     watcher_ip: String,
-    reload_tx: Sender<()>,
-) -> Result<RecommendedWatcher> {
-    let build_manager = BuildManager {
-        config: config.clone(),
-        reload_tx,
-    };
-
-    let mut last_update_time = chrono::Local::now().timestamp();
-
+    build_manager: Arc<BuildManager>,
+) -> Result<RecommendedWatcher, ServeError> {
     // file watcher: check file change
-    let allow_watch_path = config
-        .dioxus_config
-        .web
-        .watcher
-        .watch_path
-        .clone()
-        .unwrap_or_else(|| vec![PathBuf::from("src")]);
+    let mut reload_trigger = ReloadTrigger::new(config);
+    let paths = watch_paths(config, &reload_trigger);
+    let probe = WatchProbe::new(config, &paths);
+    if config.show_diffs {
+        diffs::snapshot(&paths);
+    }
 
     let watcher_config = config.clone();
+    let watcher_probe = probe.clone();
     let mut watcher = notify::recommended_watcher(move |info: notify::Result<notify::Event>| {
         let config = watcher_config.clone();
-        if let Ok(e) = info {
-            if chrono::Local::now().timestamp() > last_update_time {
-                match build_manager.rebuild() {
-                    Ok(res) => {
-                        last_update_time = chrono::Local::now().timestamp();
-
-                        #[allow(clippy::redundant_clone)]
-                        print_console_info(
-                            &watcher_ip,
-                            port,
-                            &config,
-                            PrettierOptions {
-                                changed: e.paths.clone(),
-                                warnings: res.warnings,
-                                elapsed_time: res.elapsed_time,
-                            },
-                        );
-
-                        #[cfg(feature = "plugin")]
-                        let _ = PluginManager::on_serve_rebuild(
-                            chrono::Local::now().timestamp(),
-                            e.paths,
-                        );
-                    }
-                    Err(e) => log::error!("{}", e),
+        if build_manager.is_paused() {
+            return;
+        }
+        if let Ok(mut e) = info {
+            if watcher_probe.observe(&e.paths) {
+                return;
+            }
+            if handle_env_change(&build_manager, &e.paths)
+                || handle_extra_files_change(&build_manager, &e.paths)
+                || handle_locale_change(&build_manager, &e.paths)
+            {
+                return;
+            }
+            if !keep_watched_extensions(&config.dioxus_config.web.watcher, &mut e.paths) {
+                return;
+            }
+            if reload_trigger.handles_all(&e.paths) {
+                if reload_trigger.should_reload(&e.paths) {
+                    let _ = build_manager.reload_tx.send(ReloadMessage::Reload);
                 }
+                return;
             }
+            schedule_rebuild(&build_manager, &watcher_ip, port, &config, e.paths);
         }
-    })
-    .unwrap();
+    })?;
 
-    for sub_path in allow_watch_path {
+    for path in paths {
         watcher
-            .watch(
-                &config.crate_dir.join(sub_path),
-                notify::RecursiveMode::Recursive,
-            )
-            .unwrap();
+            .watch(&path, watch_mode(&path))
+            .map_err(|source| ServeError::WatchPathFailed { path, source })?;
     }
+    watch_dotenv(&mut watcher, config);
+    watch_extra_files(&mut watcher, config);
+    watch_locales(&mut watcher, config);
+    probe.run();
     Ok(watcher)
 }
 
@@ -507,106 +1609,534 @@ async fn setup_file_watcher_hot_reload(
     port: u16,
     watcher_ip: String,
     hot_reload_tx: Sender<Template<'static>>,
-    file_map: Arc<Mutex<FileMap<HtmlCtx>>>,
+    file_map: Arc<Mutex<CrateFileMaps>>,
     build_manager: Arc<BuildManager>,
-) -> Result<RecommendedWatcher> {
+) -> Result<RecommendedWatcher, ServeError> {
     // file watcher: check file change
-    let allow_watch_path = config
-        .dioxus_config
-        .web
-        .watcher
-        .watch_path
-        .clone()
-        .unwrap_or_else(|| vec![PathBuf::from("src")]);
+    let mut reload_trigger = ReloadTrigger::new(config);
+    let paths = watch_paths(config, &reload_trigger);
+    let strategy = StrategyMatrix::from_config(config);
+    let probe = WatchProbe::new(config, &paths);
+    if config.show_diffs {
+        diffs::snapshot(&paths);
+    }
 
     let watcher_config = config.clone();
-    let mut last_update_time = chrono::Local::now().timestamp();
+    let watcher_probe = probe.clone();
 
     let mut watcher = RecommendedWatcher::new(
-        move |evt: notify::Result<notify::Event>| {
+        move |mut evt: notify::Result<notify::Event>| {
             let config = watcher_config.clone();
+            if build_manager.is_paused() {
+                return;
+            }
+            if matches!(&evt, Ok(evt) if watcher_probe.observe(&evt.paths)) {
+                return;
+            }
             // Give time for the change to take effect before reading the file
             std::thread::sleep(std::time::Duration::from_millis(100));
-            if chrono::Local::now().timestamp() > last_update_time {
-                if let Ok(evt) = evt {
-                    let mut messages: Vec<Template<'static>> = Vec::new();
-                    for path in evt.paths.clone() {
-                        // if this is not a rust file, rebuild the whole project
-                        if path.extension().and_then(|p| p.to_str()) != Some("rs") {
-                            match build_manager.rebuild() {
-                                Ok(res) => {
-                                    print_console_info(
+            if let Ok(evt) = &mut evt {
+                if handle_env_change(&build_manager, &evt.paths)
+                    || handle_extra_files_change(&build_manager, &evt.paths)
+                    || handle_locale_change(&build_manager, &evt.paths)
+                {
+                    return;
+                }
+                if !keep_watched_extensions(&config.dioxus_config.web.watcher, &mut evt.paths) {
+                    return;
+                }
+                if reload_trigger.handles_all(&evt.paths) {
+                    if reload_trigger.should_reload(&evt.paths) {
+                        let _ = build_manager.reload_tx.send(ReloadMessage::Reload);
+                    }
+                    return;
+                }
+            }
+            if let Ok(evt) = evt {
+                let mut messages: Vec<Template<'static>> = Vec::new();
+                let mut reload = None;
+                for path in evt.paths.clone() {
+                    let strategy = match strategy.strategy_for(&path) {
+                        // only files from the asset directory have a place in the output directory
+                        ReloadStrategy::AssetCopy | ReloadStrategy::CssInject
+                            if !path.starts_with(&config.asset_dir) =>
+                        {
+                            ReloadStrategy::Rebuild
+                        }
+                        strategy => strategy,
+                    };
+
+                    match strategy {
+                        ReloadStrategy::Ignore => {}
+                        ReloadStrategy::ReloadOnly => reload = Some(ReloadMessage::Reload),
+                        ReloadStrategy::AssetCopy | ReloadStrategy::CssInject => {
+                            if let Err(err) = copy_asset(&config, &path) {
+                                log::error!("Failed to copy {}: {}", path.display(), err);
+                                continue;
+                            }
+                            reload = Some(asset_reload(reload, strategy));
+                        }
+                        ReloadStrategy::Rebuild => {
+                            schedule_rebuild(&build_manager, &watcher_ip, port, &config, evt.paths);
+                            return;
+                        }
+                        ReloadStrategy::RsxHotreload => {
+                            // Other events wait for the file maps, so only lock them once
+                            // the file is readable
+                            wait_until_parsed(&path, &config.dioxus_config.web.watcher);
+
+                            // find changes to the rsx in the file
+                            let mut maps = file_map.lock().unwrap();
+                            let Some((crate_dir, map)) = maps.crate_of(&path) else {
+                                continue;
+                            };
+
+                            match map.update_rsx(&path, crate_dir) {
+                                Ok(UpdateResult::UpdatedRsx(msgs)) => {
+                                    messages.extend(msgs);
+                                }
+                                Ok(UpdateResult::NeedsRebuild) => {
+                                    drop(maps);
+                                    schedule_rebuild(
+                                        &build_manager,
                                         &watcher_ip,
                                         port,
                                         &config,
-                                        PrettierOptions {
-                                            changed: evt.paths,
-                                            warnings: res.warnings,
-                                            elapsed_time: res.elapsed_time,
-                                        },
+                                        evt.paths,
                                     );
+                                    return;
                                 }
                                 Err(err) => {
                                     log::error!("{}", err);
                                 }
                             }
-                            return;
                         }
-                        // find changes to the rsx in the file
-                        let mut map = file_map.lock().unwrap();
-
-                        match map.update_rsx(&path, &config.crate_dir) {
-                            Ok(UpdateResult::UpdatedRsx(msgs)) => {
-                                messages.extend(msgs);
-                            }
-                            Ok(UpdateResult::NeedsRebuild) => {
-                                match build_manager.rebuild() {
-                                    Ok(res) => {
-                                        print_console_info(
-                                            &watcher_ip,
-                                            port,
-                                            &config,
-                                            PrettierOptions {
-                                                changed: evt.paths,
-                                                warnings: res.warnings,
-                                                elapsed_time: res.elapsed_time,
-                                            },
-                                        );
-                                    }
-                                    Err(err) => {
-                                        log::error!("{}", err);
-                                    }
-                                }
-                                return;
-                            }
-                            Err(err) => {
-                                log::error!("{}", err);
-                            }
-                        }
-                    }
-                    for msg in messages {
-                        let _ = hot_reload_tx.send(msg);
                     }
                 }
-                last_update_time = chrono::Local::now().timestamp();
+                if !messages.is_empty() || reload.is_some() {
+                    bell::ring(&config);
+                }
+                for msg in messages {
+                    let _ = hot_reload_tx.send(msg);
+                }
+                if let Some(reload) = reload {
+                    let _ = build_manager.reload_tx.send(reload);
+                }
             }
         },
         notify::Config::default(),
-    )
-    .unwrap();
+    )?;
 
-    for sub_path in allow_watch_path {
-        if let Err(err) = watcher.watch(
-            &config.crate_dir.join(&sub_path),
-            notify::RecursiveMode::Recursive,
-        ) {
-            log::error!("error watching {sub_path:?}: \n{}", err);
-        }
+    let mut summary = WatchSummary::default();
+    for path in paths {
+        let result = watcher.watch(&path, watch_mode(&path));
+        summary.record(path, result);
     }
+    summary.log();
+    watch_dotenv(&mut watcher, config);
+    watch_extra_files(&mut watcher, config);
+    watch_locales(&mut watcher, config);
+    probe.run();
 
     Ok(watcher)
 }
 
+/// The absolute paths the watcher watches recursively, the `watch_path`s and the
+/// `reload_only_paths`
+fn watch_paths(config: &CrateConfig, reload_trigger: &ReloadTrigger) -> Vec<PathBuf> {
+    let watcher_config = &config.dioxus_config.web.watcher;
+    let paths = watcher_config
+        .watch_path
+        .clone()
+        .unwrap_or_else(|| vec![PathBuf::from("src")])
+        .into_iter()
+        .chain(
+            watcher_config
+                .groups
+                .iter()
+                .flatten()
+                .flat_map(|group| group.paths.iter().cloned()),
+        )
+        .map(|path| config.crate_dir.join(path))
+        .chain(
+            crates::hot_reload_crates(config)
+                .into_iter()
+                .map(|crate_dir| crate_dir.join("src")),
+        )
+        .chain(reload_trigger.paths().iter().cloned())
+        .collect();
+
+    if watcher_config.follow_symlinks.unwrap_or(false) {
+        resolve_symlinks(paths)
+    } else {
+        paths
+    }
+}
+
+/// Replace symlinked paths with the paths they point to, and add the real paths of the
+/// symlinked directories inside them, which the recursive watch doesn't follow.
+///
+/// Paths without a symlink are kept as they are, so their events still match the configured
+/// paths.
+fn resolve_symlinks(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut resolved = Vec::new();
+    let mut pending = paths;
+    while let Some(path) = pending.pop() {
+        let through_symlink = path.ancestors().any(|ancestor| ancestor.is_symlink());
+        let real = match through_symlink {
+            // Leave missing paths to the watcher to report
+            true => path.canonicalize().unwrap_or_else(|_| path.clone()),
+            false => path.clone(),
+        };
+        if resolved.contains(&real) {
+            continue;
+        }
+        if real != path && path.is_symlink() {
+            log::info!("🔗 Watching {} for {}", real.display(), path.display());
+        }
+
+        for entry in walkdir::WalkDir::new(&real)
+            .min_depth(1)
+            .into_iter()
+            .flatten()
+        {
+            if entry.path_is_symlink() && entry.path().is_dir() {
+                pending.push(entry.into_path());
+            }
+        }
+        resolved.push(real);
+    }
+    resolved
+}
+
+/// Drop the changed paths whose extension isn't one of the `watch_extensions`, if set.
+///
+/// Returns false if no path is left to handle.
+fn keep_watched_extensions(watcher_config: &WebWatcherConfig, paths: &mut Vec<PathBuf>) -> bool {
+    let Some(extensions) = &watcher_config.watch_extensions else {
+        return true;
+    };
+    paths.retain(|path| {
+        let Some(extension) = path.extension().and_then(|ext| ext.to_str()) else {
+            return false;
+        };
+        extensions.iter().any(|allowed| {
+            allowed
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(extension)
+        })
+    });
+    !paths.is_empty()
+}
+
+/// Watch directories recursively and files on their own, recursive watches of a file behave
+/// differently from one platform to the next
+fn watch_mode(path: &Path) -> notify::RecursiveMode {
+    let mode = if path.is_file() {
+        notify::RecursiveMode::NonRecursive
+    } else {
+        notify::RecursiveMode::Recursive
+    };
+    log::debug!("Watching {} ({:?})", path.display(), mode);
+    mode
+}
+
+/// Which of the configured paths the hot reload watcher watches and why the others failed,
+/// logged once it is set up instead of failing one by one
+#[derive(Debug, Default)]
+struct WatchSummary {
+    watched: Vec<PathBuf>,
+    failed: Vec<(PathBuf, String)>,
+}
+
+impl WatchSummary {
+    fn record(&mut self, path: PathBuf, result: notify::Result<()>) {
+        match result {
+            Ok(()) => self.watched.push(path),
+            Err(err) => self.failed.push((path, err.to_string())),
+        }
+    }
+
+    fn log(&self) {
+        for (level, message) in self.messages() {
+            log::log!(level, "{}", message);
+        }
+    }
+
+    /// What to log about the watched and failed paths, with the level to log it at
+    fn messages(&self) -> Vec<(log::Level, String)> {
+        let list = |paths: &mut dyn Iterator<Item = &PathBuf>| {
+            paths
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut messages = Vec::new();
+        if !self.watched.is_empty() {
+            messages.push((
+                log::Level::Info,
+                format!(
+                    "👀 Watching {} paths: {}",
+                    self.watched.len(),
+                    list(&mut self.watched.iter())
+                ),
+            ));
+        }
+        for (path, reason) in &self.failed {
+            messages.push((
+                log::Level::Warn,
+                format!("Failed to watch {}: {}", path.display(), reason),
+            ));
+        }
+        if self.watched.is_empty() {
+            messages.push((
+                log::Level::Error,
+                format!(
+                    "🚫 None of the watch paths could be watched ({}), changes won't hot reload or rebuild until serve restarts",
+                    list(&mut self.failed.iter().map(|(path, _)| path))
+                ),
+            ));
+        }
+        messages
+    }
+}
+
+/// Watch the sources of the `web.serve.extra_files`
+fn watch_extra_files(watcher: &mut RecommendedWatcher, config: &CrateConfig) {
+    for source in extra_files::sources(config) {
+        if let Err(err) = watcher.watch(&source, notify::RecursiveMode::NonRecursive) {
+            log::warn!("error watching {}: {}", source.display(), err);
+        }
+    }
+}
+
+/// Copy the extra files again and reload when only their sources changed.
+///
+/// Returns true if the change was handled.
+fn handle_extra_files_change(build_manager: &BuildManager, changed: &[PathBuf]) -> bool {
+    let config = &build_manager.config;
+    if changed.is_empty()
+        || !changed
+            .iter()
+            .all(|path| extra_files::is_source(config, path))
+    {
+        return false;
+    }
+
+    extra_files::copy_changed(config, changed);
+    let _ = build_manager.reload_tx.send(ReloadMessage::Reload);
+    true
+}
+
+/// Watch the `web.watcher.locale_paths`, which may be outside the watch paths
+fn watch_locales(watcher: &mut RecommendedWatcher, config: &CrateConfig) {
+    for path in locales::paths(config) {
+        if let Err(err) = watcher.watch(&path, watch_mode(&path)) {
+            log::warn!("error watching {}: {}", path.display(), err);
+        }
+    }
+}
+
+/// Copy the changed locale files and reload without rebuilding, the app loads them at runtime.
+///
+/// Returns true if the change was handled.
+fn handle_locale_change(build_manager: &BuildManager, changed: &[PathBuf]) -> bool {
+    if !locales::sync_changed(&build_manager.config, changed) {
+        return false;
+    }
+    log::info!("🌐 Locale files changed, reloading");
+    let _ = build_manager.reload_tx.send(ReloadMessage::Reload);
+    true
+}
+
+/// Watch the `.env` file of the crate, whose variables are injected into the dev page
+fn watch_dotenv(watcher: &mut RecommendedWatcher, config: &CrateConfig) {
+    let dotenv = config.crate_dir.join(".env");
+    if dotenv.is_file() {
+        if let Err(err) = watcher.watch(&dotenv, notify::RecursiveMode::NonRecursive) {
+            log::warn!("error watching {}: {}", dotenv.display(), err);
+        }
+    }
+}
+
+/// Regenerate the dev page and reload when only the `.env` file changed.
+///
+/// Returns true if the change was handled.
+fn handle_env_change(build_manager: &BuildManager, changed: &[PathBuf]) -> bool {
+    let is_dotenv =
+        |path: &PathBuf| path.file_name().and_then(|name| name.to_str()) == Some(".env");
+    if changed.is_empty() || !changed.iter().all(is_dotenv) {
+        return false;
+    }
+
+    log::info!("🌱 .env changed, regenerating the dev page");
+    match Serve::regen_dev_page(&build_manager.config) {
+        Ok(()) => {
+            let _ = build_manager.reload_tx.send(ReloadMessage::Reload);
+        }
+        Err(err) => log::error!("{}", err),
+    }
+    true
+}
+
+/// How long to wait for more events after a change before starting a full rebuild
+const REBUILD_SETTLE: Duration = Duration::from_millis(100);
+
+/// Rebuild the project now, or queue the rebuild if the last one was too recent
+fn schedule_rebuild(
+    build_manager: &Arc<BuildManager>,
+    watcher_ip: &String,
+    port: u16,
+    config: &CrateConfig,
+    changed: Vec<PathBuf>,
+) {
+    let decision = build_manager
+        .cooldown
+        .lock()
+        .unwrap()
+        .request(Instant::now(), changed);
+    let delay = match decision {
+        CooldownDecision::Run(changed) => {
+            rebuild_and_print(build_manager, watcher_ip, port, config, changed);
+            build_manager
+                .cooldown
+                .lock()
+                .unwrap()
+                .finish(Instant::now())
+        }
+        CooldownDecision::Defer(delay) => Some(delay),
+        CooldownDecision::Queued => None,
+    };
+
+    if let Some(delay) = delay {
+        if delay > REBUILD_SETTLE {
+            log::info!(
+                "⏳ Rebuild queued, waiting {} millis for the rebuild cooldown",
+                delay.as_millis()
+            );
+        }
+        let build_manager = build_manager.clone();
+        let watcher_ip = watcher_ip.clone();
+        let config = config.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            schedule_pending_rebuild(build_manager, watcher_ip, port, config);
+        });
+    }
+}
+
+/// Run the rebuild queued during the cooldown and any rebuild queued while it ran
+fn schedule_pending_rebuild(
+    build_manager: Arc<BuildManager>,
+    watcher_ip: String,
+    port: u16,
+    config: CrateConfig,
+) {
+    let pending = build_manager.cooldown.lock().unwrap().take_pending();
+    if let Some(changed) = pending {
+        rebuild_and_print(&build_manager, &watcher_ip, port, &config, changed);
+        let delay = build_manager
+            .cooldown
+            .lock()
+            .unwrap()
+            .finish(Instant::now());
+        if let Some(delay) = delay {
+            std::thread::sleep(delay);
+            schedule_pending_rebuild(build_manager, watcher_ip, port, config);
+        }
+    }
+}
+
+/// Rebuild the project and print the result to the console
+fn rebuild_and_print(
+    build_manager: &BuildManager,
+    watcher_ip: &String,
+    port: u16,
+    config: &CrateConfig,
+    changed: Vec<PathBuf>,
+) {
+    let reload = StrategyMatrix::from_config(config).affects_frontend(&changed);
+    match build_manager.rebuild_and_reload(reload) {
+        Ok(res) => {
+            #[allow(clippy::redundant_clone)]
+            print_console_info(
+                watcher_ip,
+                port,
+                config,
+                PrettierOptions {
+                    changed: changed.clone(),
+                    warnings: res.warnings,
+                    elapsed_time: res.elapsed_time,
+                    phases: res.phases,
+                },
+            );
+            bell::ring(config);
+
+            #[cfg(feature = "plugin")]
+            if config.plugins_enabled {
+                let _ = PluginManager::on_serve_rebuild(chrono::Local::now().timestamp(), changed);
+            }
+        }
+        Err(err) => {
+            log::error!("{}", err);
+        }
+    }
+}
+
+/// Copy a changed file from the asset directory to the same place in the output directory,
+/// removing it from the output directory if it was deleted
+fn copy_asset(config: &CrateConfig, path: &Path) -> std::io::Result<()> {
+    let Ok(relative) = path.strip_prefix(&config.asset_dir) else {
+        return Ok(());
+    };
+    let target = config.out_dir.join(relative);
+
+    if !path.exists() {
+        if target.is_file() {
+            std::fs::remove_file(target)?;
+        }
+        return Ok(());
+    }
+
+    if path.is_file() {
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(path, target)?;
+    }
+    Ok(())
+}
+
+/// The reload of a batch of changes after one more asset was copied. Stylesheets are injected
+/// unless another change of the batch needs a full reload.
+fn asset_reload(reload: Option<ReloadMessage>, strategy: ReloadStrategy) -> ReloadMessage {
+    if strategy == ReloadStrategy::AssetCopy || reload == Some(ReloadMessage::Reload) {
+        ReloadMessage::Reload
+    } else {
+        ReloadMessage::CssInject
+    }
+}
+
+/// Re-read a changed file a few times until it parses, before its rsx is updated.
+///
+/// Editors may write a file in several chunks, so the watcher can fire while the file is
+/// still in a broken intermediate state. If it still doesn't parse the update surfaces the
+/// error.
+fn wait_until_parsed(path: &Path, watcher_config: &WebWatcherConfig) {
+    let retries = watcher_config.rsx_read_retries.unwrap_or(3);
+    let delay = Duration::from_millis(watcher_config.rsx_read_retry_delay.unwrap_or(50));
+
+    for _ in 0..retries {
+        let parses = std::fs::read_to_string(path)
+            .map(|src| syn::parse_file(&src).is_ok())
+            .unwrap_or(false);
+        if parses {
+            break;
+        }
+        std::thread::sleep(delay);
+    }
+}
+
 /// Get the network ip
 fn get_ip() -> Option<String> {
     let socket = match UdpSocket::bind("0.0.0.0:0") {
@@ -625,23 +2155,61 @@ fn get_ip() -> Option<String> {
     }
 }
 
+/// The websocket subprotocol of the reload channels. Some proxies drop upgrades that don't
+/// negotiate a subprotocol, so it is echoed back whenever a client asks for it.
+pub const RELOAD_PROTOCOL: &str = "dioxus-reload";
+
 /// Handle websockets
 async fn ws_handler(
     ws: WebSocketUpgrade,
     _: Option<TypedHeader<headers::UserAgent>>,
     Extension(state): Extension<Arc<WsReloadState>>,
 ) -> impl IntoResponse {
+    let ws = ws.protocols([RELOAD_PROTOCOL]);
     ws.on_upgrade(|mut socket| async move {
+        dashboard_client_connected();
+        metrics::reload_client_connected();
+        // Every client reads the channel at its own pace, so a slow one only falls behind on
+        // its own and never holds up the others
         let mut rx = state.update.subscribe();
         let reload_watcher = tokio::spawn(async move {
             loop {
-                rx.recv().await.unwrap();
-                // ignore the error
-                if socket
-                    .send(Message::Text(String::from("reload")))
-                    .await
-                    .is_err()
-                {
+                let msg = match rx.recv().await {
+                    Ok(msg) => msg,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        match state.slow_client_policy {
+                            SlowClientPolicy::Drop => {
+                                log::warn!(
+                                    "🐢 Dropping a reload client that fell {} messages behind",
+                                    skipped
+                                );
+                                break;
+                            }
+                            // The missed messages would have ended in a reload anyway
+                            SlowClientPolicy::Wait => ReloadMessage::Reload,
+                        }
+                    }
+                };
+                let send = socket.send(Message::Text(String::from(state.text(msg))));
+                let sent = match state.slow_client_policy {
+                    SlowClientPolicy::Wait => send.await.is_ok(),
+                    SlowClientPolicy::Drop => {
+                        match tokio::time::timeout(SLOW_CLIENT_TIMEOUT, send).await {
+                            Ok(sent) => sent.is_ok(),
+                            Err(_) => {
+                                log::warn!(
+                                    "🐢 Dropping a reload client that took over {}s to take a message",
+                                    SLOW_CLIENT_TIMEOUT.as_secs()
+                                );
+                                break;
+                            }
+                        }
+                    }
+                };
+                // The socket is closed once a send failed, the page reconnects after
+                // `reload_grace_ms` and reloads to catch up
+                if !sent {
                     break;
                 }
 
@@ -651,5 +2219,434 @@ async fn ws_handler(
         });
 
         reload_watcher.await.unwrap();
+        dashboard_client_disconnected();
+        metrics::reload_client_disconnected();
     })
 }
+
+/// Serve `router` on a free local port, until the returned handle is aborted
+#[cfg(test)]
+pub(crate) fn spawn_router(router: Router) -> (SocketAddr, tokio::task::JoinHandle<()>) {
+    let server = axum::Server::bind(&"127.0.0.1:0".parse().unwrap())
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>());
+    let addr = server.local_addr();
+    (addr, tokio::spawn(async move { server.await.unwrap() }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use axum::http::header::{
+        ACCEPT_ENCODING, ACCEPT_RANGES, CONTENT_ENCODING, CONTENT_RANGE, RANGE,
+    };
+    use tokio_tungstenite::tungstenite::{Error as WsError, Message as WsMessage};
+
+    /// Serve a 1000 byte file and a dev page with the router of `dx serve` and request a path,
+    /// optionally a range of it
+    async fn fetch(
+        compression_level: Option<CompressionLevel>,
+        path: &str,
+        range: Option<&str>,
+    ) -> hyper::Response<hyper::Body> {
+        fetch_with(
+            |config| config.dioxus_config.web.serve.compression_level = compression_level,
+            path,
+            range,
+        )
+        .await
+    }
+
+    /// [`fetch`] with a config changed by `configure`
+    async fn fetch_with(
+        configure: impl FnOnce(&mut CrateConfig),
+        path: &str,
+        range: Option<&str>,
+    ) -> hyper::Response<hyper::Body> {
+        let out_dir = tempfile::tempdir().unwrap();
+        std::fs::write(out_dir.path().join("video.mp4"), "0123456789".repeat(100)).unwrap();
+        let dev_page = format!(
+            "<html><body><script>{}</script></body></html>",
+            include_str!("../assets/autoreload.js")
+        );
+        std::fs::write(out_dir.path().join("index.html"), dev_page).unwrap();
+
+        let mut config = CrateConfig::new(None).unwrap();
+        config.out_dir = out_dir.path().to_path_buf();
+        configure(&mut config);
+
+        let (reload_tx, _) = broadcast::channel(1);
+        let ws_reload = Arc::new(WsReloadState::new(reload_tx, &config));
+        let build_status = Arc::new(Mutex::new(BuildStatus::new(BuildState::Ok)));
+        let files = DiskFiles::shared(&config);
+        let router = setup_router(config, files, ws_reload, None, None, build_status, None)
+            .await
+            .unwrap();
+        let (addr, handle) = spawn_router(router);
+
+        let mut req =
+            Request::get(format!("http://{}{}", addr, path)).header(ACCEPT_ENCODING, "gzip");
+        if let Some(range) = range {
+            req = req.header(RANGE, range);
+        }
+        let req = req.body(hyper::Body::empty()).unwrap();
+        let resp = hyper::Client::new().request(req).await.unwrap();
+
+        // Read the body while the out dir is still around
+        let (parts, body) = resp.into_parts();
+        let body = hyper::body::to_bytes(body).await.unwrap();
+        handle.abort();
+        hyper::Response::from_parts(parts, body.into())
+    }
+
+    #[tokio::test]
+    async fn range_requests_return_the_slice() {
+        let resp = fetch(None, "/video.mp4", Some("bytes=2-5")).await;
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(resp.headers()[ACCEPT_RANGES], "bytes");
+        assert_eq!(resp.headers()[CONTENT_RANGE], "bytes 2-5/1000");
+        assert_eq!(
+            hyper::body::to_bytes(resp.into_body()).await.unwrap(),
+            "2345"
+        );
+    }
+
+    #[tokio::test]
+    async fn range_requests_are_not_compressed() {
+        let resp = fetch(
+            Some(CompressionLevel::Best),
+            "/video.mp4",
+            Some("bytes=0-499"),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+        assert_eq!(resp.headers()[CONTENT_RANGE], "bytes 0-499/1000");
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        assert_eq!(body.len(), 500);
+        assert!(body.starts_with(b"0123456789"));
+    }
+
+    #[test]
+    fn css_only_batches_are_injected() {
+        let rule = |pattern: &str, action| crate::WatcherStrategyRule {
+            pattern: pattern.to_string(),
+            action,
+        };
+        let strategy = StrategyMatrix::new(
+            PathBuf::from("/app"),
+            &[
+                rule("**/*.css", ReloadStrategy::CssInject),
+                rule("public/**", ReloadStrategy::AssetCopy),
+            ],
+        );
+        // The paths of a single event, like the watcher gets them
+        let batch = |paths: &[&str]| {
+            paths.iter().fold(None, |reload, path| {
+                Some(asset_reload(reload, strategy.strategy_for(Path::new(path))))
+            })
+        };
+        assert_eq!(
+            batch(&["/app/public/main.css", "/app/public/theme.css"]),
+            Some(ReloadMessage::CssInject)
+        );
+        assert_eq!(
+            batch(&["/app/public/main.css", "/app/public/logo.png"]),
+            Some(ReloadMessage::Reload)
+        );
+        assert_eq!(
+            batch(&["/app/public/logo.png", "/app/public/main.css"]),
+            Some(ReloadMessage::Reload)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_symlinked_paths_are_resolved() {
+        let dir = tempfile::tempdir().unwrap();
+        // The temporary directory itself may be behind a symlink, e.g. on macOS
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("real")).unwrap();
+        std::fs::create_dir_all(root.join("plain")).unwrap();
+        std::os::unix::fs::symlink(root.join("real"), root.join("link")).unwrap();
+
+        let plain = root.join("real").join("..").join("plain");
+        let resolved = resolve_symlinks(vec![plain.clone(), root.join("link")]);
+        assert_eq!(resolved.len(), 2, "{:?}", resolved);
+        assert!(resolved.contains(&plain), "{:?}", resolved);
+        assert!(resolved.contains(&root.join("real")), "{:?}", resolved);
+    }
+
+    #[tokio::test]
+    async fn reload_client_is_compressed() {
+        let resp = fetch(Some(CompressionLevel::Fastest), "/", None).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[CONTENT_ENCODING], "gzip");
+
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        let mut page = String::new();
+        std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(&body[..]), &mut page)
+            .unwrap();
+        assert!(page.contains("/_dioxus/ws"));
+
+        // The plain http control routes are compressed too, the build status is too small
+        let resp = fetch(Some(CompressionLevel::Fastest), "/_dioxus", None).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[CONTENT_ENCODING], "gzip");
+
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        let mut routes = String::new();
+        std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(&body[..]), &mut routes)
+            .unwrap();
+        assert!(routes.contains("/_dioxus/status"), "{}", routes);
+    }
+
+    #[tokio::test]
+    async fn backend_404s_are_not_replaced_by_the_index() {
+        let backend = Router::new().route(
+            "/api/*path",
+            get(|| async { (StatusCode::NOT_FOUND, "no such user") }),
+        );
+        let (backend_addr, backend_handle) = spawn_router(backend);
+
+        let resp = fetch_with(
+            |config| {
+                config.dioxus_config.web.watcher.index_on_404 = Some(true);
+                config.dioxus_config.web.proxy = Some(vec![crate::WebProxyConfig {
+                    backend: format!("http://{}/api", backend_addr),
+                    ..Default::default()
+                }]);
+            },
+            "/api/users/1",
+            None,
+        )
+        .await;
+        backend_handle.abort();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            hyper::body::to_bytes(resp.into_body()).await.unwrap(),
+            "no such user"
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_favicon_is_no_content() {
+        let resp = fetch(None, "/favicon.ico", None).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    }
+
+    /// Connect a reload client with `policy`, and send `messages` through a channel of one
+    /// message before the client reads any, returning what it reads next
+    async fn lag_reload_client(
+        policy: SlowClientPolicy,
+        messages: &[ReloadMessage],
+    ) -> Option<Result<WsMessage, WsError>> {
+        use futures::StreamExt;
+
+        let (update, _) = broadcast::channel(1);
+        let state = Arc::new(WsReloadState {
+            update: update.clone(),
+            focused_only: false,
+            slow_client_policy: policy,
+        });
+        let router = Router::new()
+            .route("/_dioxus/ws", get(ws_handler))
+            .layer(Extension(state));
+        let (addr, handle) = spawn_router(router);
+
+        let url = format!("ws://{}/_dioxus/ws", addr);
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        while update.receiver_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        // Nothing yields in between, so the handler only gets to read once both are sent
+        for msg in messages {
+            update.send(*msg).unwrap();
+        }
+        let next = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .unwrap();
+        handle.abort();
+        next
+    }
+
+    #[tokio::test]
+    async fn lagging_clients_are_dropped() {
+        let next = lag_reload_client(
+            SlowClientPolicy::Drop,
+            &[ReloadMessage::Building, ReloadMessage::Built],
+        )
+        .await;
+        assert!(!matches!(next, Some(Ok(WsMessage::Text(_)))), "{:?}", next);
+    }
+
+    #[tokio::test]
+    async fn waited_for_clients_reload_after_lagging() {
+        let next = lag_reload_client(
+            SlowClientPolicy::Wait,
+            &[ReloadMessage::Building, ReloadMessage::Built],
+        )
+        .await;
+        assert!(
+            matches!(&next, Some(Ok(WsMessage::Text(text))) if text == "reload"),
+            "{:?}",
+            next
+        );
+    }
+
+    #[test]
+    fn focused_only_reloads_are_marked() {
+        let (update, _) = broadcast::channel(1);
+        let mut state = WsReloadState {
+            update,
+            focused_only: false,
+            slow_client_policy: SlowClientPolicy::Drop,
+        };
+        assert_eq!(state.text(ReloadMessage::Reload), "reload");
+
+        state.focused_only = true;
+        assert_eq!(state.text(ReloadMessage::Reload), "reload-focused");
+        assert_eq!(state.text(ReloadMessage::CssInject), "css");
+    }
+
+    #[test]
+    fn out_dirs_holding_the_crate_are_not_cleaned() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = CrateConfig::new(None).unwrap();
+        config.crate_dir = dir.path().to_path_buf();
+        config.asset_dir = dir.path().join("public");
+
+        config.out_dir = dir.path().join("dist");
+        assert_eq!(clean_out_dir_conflict(&config), None);
+        config.out_dir = PathBuf::from(".");
+        assert!(clean_out_dir_conflict(&config).is_some());
+        config.asset_dir = dir.path().join("dist").join("public");
+        config.out_dir = dir.path().join("dist");
+        assert!(clean_out_dir_conflict(&config).is_some());
+    }
+
+    #[test]
+    fn staged_builds_replace_the_out_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let out_dir = dir.path().join("dist");
+        let staging = out_dir_sibling(&out_dir, "staging");
+        std::fs::create_dir_all(out_dir.join("assets")).unwrap();
+        std::fs::write(out_dir.join("assets/deleted.png"), "old").unwrap();
+        std::fs::create_dir_all(&staging).unwrap();
+        std::fs::write(staging.join("index.html"), "new").unwrap();
+
+        swap_out_dir(&out_dir, &staging);
+        assert_eq!(
+            std::fs::read_to_string(out_dir.join("index.html")).unwrap(),
+            "new"
+        );
+        assert!(!out_dir.join("assets").exists());
+        assert!(!staging.exists());
+        assert!(!out_dir_sibling(&out_dir, "stale").exists());
+    }
+
+    #[test]
+    fn only_slow_builds_are_announced() {
+        let mut config = CrateConfig::new(None).unwrap();
+        config.dioxus_config.web.serve.building_delay_ms = Some(20);
+        let (reload_tx, mut reload_rx) = broadcast::channel(4);
+        let build_manager = BuildManager::new(&config, reload_tx);
+
+        assert!(build_manager.announce_building(|| Ok(())).is_ok());
+        assert!(reload_rx.try_recv().is_err());
+
+        let slow_failure = build_manager.announce_building(|| -> Result<()> {
+            std::thread::sleep(Duration::from_millis(200));
+            Err(crate::Error::BuildFailed("failed".to_string()))
+        });
+        assert!(slow_failure.is_err());
+        assert_eq!(reload_rx.try_recv().unwrap(), ReloadMessage::Building);
+        assert_eq!(reload_rx.try_recv().unwrap(), ReloadMessage::Built);
+
+        let slow_success = build_manager.announce_building(|| -> Result<()> {
+            std::thread::sleep(Duration::from_millis(200));
+            Ok(())
+        });
+        assert!(slow_success.is_ok());
+        assert_eq!(reload_rx.try_recv().unwrap(), ReloadMessage::Building);
+        assert_eq!(reload_rx.try_recv().unwrap(), ReloadMessage::Built);
+    }
+
+    #[test]
+    fn watch_failures_are_summarized() {
+        let mut summary = WatchSummary::default();
+        summary.record(PathBuf::from("src"), Ok(()));
+        summary.record(
+            PathBuf::from("missing"),
+            Err(notify::Error::path_not_found()),
+        );
+        assert_eq!(summary.watched, vec![PathBuf::from("src")]);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].0, PathBuf::from("missing"));
+        let levels: Vec<_> = summary
+            .messages()
+            .into_iter()
+            .map(|(level, _)| level)
+            .collect();
+        assert_eq!(levels, [log::Level::Info, log::Level::Warn]);
+
+        // Nothing watched at all is an error naming every path that failed
+        let mut summary = WatchSummary::default();
+        for path in ["src", "assets"] {
+            summary.record(PathBuf::from(path), Err(notify::Error::path_not_found()));
+        }
+        let messages = summary.messages();
+        let (level, message) = messages.last().unwrap();
+        assert_eq!(*level, log::Level::Error);
+        assert!(message.contains("None of the watch paths"), "{}", message);
+        assert!(message.contains("src, assets"), "{}", message);
+    }
+
+    #[test]
+    fn only_paths_with_watched_extensions_are_kept() {
+        let mut watcher_config = WebWatcherConfig::default();
+        let mut paths = vec![PathBuf::from("src/main.rs"), PathBuf::from("notes.txt")];
+        assert!(keep_watched_extensions(&watcher_config, &mut paths));
+        assert_eq!(paths.len(), 2);
+
+        watcher_config.watch_extensions = Some(vec!["rs".to_string(), ".CSS".to_string()]);
+        paths.push(PathBuf::from("assets/main.css"));
+        paths.push(PathBuf::from("Makefile"));
+        assert!(keep_watched_extensions(&watcher_config, &mut paths));
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("src/main.rs"),
+                PathBuf::from("assets/main.css")
+            ]
+        );
+
+        let mut paths = vec![PathBuf::from("notes.txt")];
+        assert!(!keep_watched_extensions(&watcher_config, &mut paths));
+    }
+
+    #[test]
+    fn out_dir_is_created_and_made_absolute() {
+        let mut config = CrateConfig::new(None).unwrap();
+        let out_dir = tempfile::tempdir().unwrap();
+        config.out_dir = out_dir.path().join("nested").join("..").join("dist");
+        resolve_out_dir(&mut config).unwrap();
+        assert_eq!(
+            config.out_dir,
+            out_dir.path().join("dist").canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn watched_files_are_not_recursive() {
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(
+            watch_mode(&manifest.join("Cargo.toml")),
+            notify::RecursiveMode::NonRecursive
+        );
+        assert_eq!(
+            watch_mode(&manifest.join("src")),
+            notify::RecursiveMode::Recursive
+        );
+    }
+}
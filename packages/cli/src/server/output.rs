@@ -1,17 +1,53 @@
 use crate::server::Diagnostic;
 use crate::CrateConfig;
 use colored::Colorize;
+use crossterm::{
+    cursor::{MoveTo, RestorePosition, SavePosition},
+    queue,
+    style::Print,
+    terminal::{Clear, ClearType},
+};
+use std::io::Write;
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::Mutex;
+
+/// The number of lines reserved at the top of the terminal for the dashboard
+const DASHBOARD_HEIGHT: u16 = 6;
+
+/// What the dashboard's fixed status region currently shows
+#[derive(Debug, Default)]
+struct DashboardState {
+    local_url: String,
+    network_url: String,
+    last_build: String,
+    elapsed_time: u128,
+    warnings: usize,
+    clients: usize,
+}
+
+lazy_static::lazy_static! {
+    static ref DASHBOARD: Mutex<Option<DashboardState>> = Mutex::new(None);
+    /// The git branch of the project, looked up once when the banner is first printed
+    static ref GIT_BRANCH: String = git_branch().unwrap_or_else(|| "unknown".to_string());
+}
 
 #[derive(Debug, Default)]
 pub struct PrettierOptions {
     pub changed: Vec<PathBuf>,
     pub warnings: Vec<Diagnostic>,
     pub elapsed_time: u128,
+    /// How many millis each phase of the build took, printed with `--profile-build`
+    pub phases: Vec<(&'static str, u128)>,
 }
 
 pub fn print_console_info(ip: &String, port: u16, config: &CrateConfig, options: PrettierOptions) {
+    // The dashboard redraws in place, which only makes sense on a terminal
+    if config.dashboard && atty::is(atty::Stream::Stdout) {
+        print_dashboard(ip, port, config, options);
+        return;
+    }
+
     if let Ok(native_clearseq) = Command::new(if cfg!(target_os = "windows") {
         "cls"
     } else {
@@ -49,8 +85,19 @@ pub fn print_console_info(ip: &String, port: u16, config: &CrateConfig, options:
     };
 
     let proxies = config.dioxus_config.web.proxy.as_ref();
+    let scheme = match config.dioxus_config.web.https.enabled {
+        Some(true) => "https",
+        _ => "http",
+    };
 
-    if options.changed.is_empty() {
+    if let (true, Some(banner)) = (
+        options.changed.is_empty(),
+        &config.dioxus_config.web.serve.banner,
+    ) {
+        let url = format!("{}://localhost:{}/", scheme, port);
+        let lan_url = format!("{}://{}:{}/", scheme, ip, port);
+        println!("{}\n", render_banner(banner, &url, &lan_url, &GIT_BRANCH));
+    } else if options.changed.is_empty() {
         println!(
             "{} @ v{} [{}] \n",
             "Dioxus".bold().green(),
@@ -68,6 +115,9 @@ pub fn print_console_info(ip: &String, port: u16, config: &CrateConfig, options:
             .purple()
             .bold()
         );
+        if config.show_diffs {
+            print_diff_stats(&options.changed);
+        }
     }
 
     if config.dioxus_config.web.https.enabled == Some(true) {
@@ -91,6 +141,13 @@ pub fn print_console_info(ip: &String, port: u16, config: &CrateConfig, options:
         );
         println!("\t> HTTPS : {}", "Disabled".to_string().red());
     }
+    if let Some(control_port) = config.dioxus_config.web.serve.control_port {
+        println!(
+            "\t> Control : {} {}",
+            format!("{}://127.0.0.1:{}/_dioxus/", scheme, control_port).blue(),
+            "(this machine only)".dimmed()
+        );
+    }
     println!();
     println!("\t> Profile : {}", profile.green());
     println!("\t> Hot Reload : {}", hot_reload.cyan());
@@ -109,6 +166,12 @@ pub fn print_console_info(ip: &String, port: u16, config: &CrateConfig, options:
         "\t> Build Time Use : {} millis",
         options.elapsed_time.to_string().green().bold()
     );
+    if config.profile_build && !options.phases.is_empty() {
+        println!("\t> Build Phases :");
+        for (phase, millis) in &options.phases {
+            println!("\t\t- {} : {} millis", phase, millis.to_string().green());
+        }
+    }
     println!();
 
     if options.warnings.is_empty() {
@@ -125,3 +188,156 @@ pub fn print_console_info(ip: &String, port: u16, config: &CrateConfig, options:
         );
     }
 }
+
+/// Print how many lines of each changed `.rs` file were added and removed, so a rebuild from a
+/// file that didn't actually change stands out
+fn print_diff_stats(changed: &[PathBuf]) {
+    for path in changed {
+        if let Some((added, removed)) = super::diffs::diff_stat(path) {
+            let stat = if added == 0 && removed == 0 {
+                "no changes".dimmed().to_string()
+            } else {
+                format!(
+                    "{} {}",
+                    format!("+{}", added).green(),
+                    format!("-{}", removed).red()
+                )
+            };
+            println!("\t\t- {} : {}", path.display(), stat);
+        }
+    }
+    println!();
+}
+
+/// Fill the placeholders of a `web.serve.banner` template
+fn render_banner(template: &str, url: &str, lan_url: &str, branch: &str) -> String {
+    template
+        .replace("{url}", url)
+        .replace("{lan_url}", lan_url)
+        .replace("{version}", crate::DIOXUS_CLI_VERSION)
+        .replace("{branch}", branch)
+}
+
+/// The current branch of the git repository the command runs in
+fn git_branch() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .output()
+        .ok()?;
+    let branch = String::from_utf8(output.stdout).ok()?;
+    let branch = branch.trim();
+    (output.status.success() && !branch.is_empty()).then(|| branch.to_string())
+}
+
+/// Update the dashboard's status region, setting it up on the first call
+fn print_dashboard(ip: &String, port: u16, config: &CrateConfig, options: PrettierOptions) {
+    let mut dashboard = DASHBOARD.lock().unwrap();
+
+    if dashboard.is_none() {
+        let (_, rows) = crossterm::terminal::size().unwrap_or((80, 24));
+        let mut stdout = std::io::stdout();
+        let _ = queue!(stdout, Clear(ClearType::All));
+        // Restrict scrolling to the area below the status region so logs don't overwrite it
+        let _ = write!(stdout, "\x1b[{};{}r", DASHBOARD_HEIGHT + 1, rows);
+        let _ = queue!(stdout, MoveTo(0, DASHBOARD_HEIGHT));
+        let _ = stdout.flush();
+    }
+
+    let state = dashboard.get_or_insert_with(DashboardState::default);
+    let scheme = if config.dioxus_config.web.https.enabled == Some(true) {
+        "https"
+    } else {
+        "http"
+    };
+    state.local_url = format!("{}://localhost:{}/", scheme, port);
+    state.network_url = format!("{}://{}:{}/", scheme, ip, port);
+    state.last_build = chrono::Local::now().format("%H:%M:%S").to_string();
+    state.elapsed_time = options.elapsed_time;
+    state.warnings = options.warnings.len();
+    draw_dashboard(state);
+
+    if !options.changed.is_empty() {
+        log::info!("Project Reloaded: Changed {} files.", options.changed.len());
+    }
+}
+
+fn draw_dashboard(state: &DashboardState) {
+    let lines = [
+        format!(
+            "{} @ v{}",
+            "Dioxus".bold().green(),
+            crate::DIOXUS_CLI_VERSION
+        ),
+        format!("\t> Local : {}", state.local_url.blue()),
+        format!("\t> Network : {}", state.network_url.blue()),
+        format!(
+            "\t> Last Build : {} ({} millis)",
+            state.last_build.dimmed(),
+            state.elapsed_time.to_string().green().bold()
+        ),
+        format!(
+            "\t> Warnings : {}    Clients : {}",
+            state.warnings.to_string().yellow(),
+            state.clients.to_string().cyan()
+        ),
+    ];
+
+    let mut stdout = std::io::stdout();
+    let _ = queue!(stdout, SavePosition);
+    for (row, line) in lines.iter().enumerate() {
+        let _ = queue!(
+            stdout,
+            MoveTo(0, row as u16),
+            Clear(ClearType::CurrentLine),
+            Print(line)
+        );
+    }
+    let _ = queue!(stdout, RestorePosition);
+    let _ = stdout.flush();
+}
+
+/// Track a reload client connecting, redrawing the dashboard if it is active
+pub fn dashboard_client_connected() {
+    if let Some(state) = DASHBOARD.lock().unwrap().as_mut() {
+        state.clients += 1;
+        draw_dashboard(state);
+    }
+}
+
+/// Track a reload client disconnecting, redrawing the dashboard if it is active
+pub fn dashboard_client_disconnected() {
+    if let Some(state) = DASHBOARD.lock().unwrap().as_mut() {
+        state.clients = state.clients.saturating_sub(1);
+        draw_dashboard(state);
+    }
+}
+
+/// Give the whole terminal back to the shell if the dashboard was drawn
+pub fn reset_dashboard() {
+    if DASHBOARD.lock().unwrap().take().is_some() {
+        print!("\x1b[r");
+        let _ = std::io::stdout().flush();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn banner_placeholders_are_filled() {
+        let banner = render_banner(
+            "{branch} @ {url} ({lan_url}) v{version} {unknown}",
+            "http://localhost:8080/",
+            "http://192.168.1.2:8080/",
+            "main",
+        );
+        assert_eq!(
+            banner,
+            format!(
+                "main @ http://localhost:8080/ (http://192.168.1.2:8080/) v{} {{unknown}}",
+                crate::DIOXUS_CLI_VERSION
+            )
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use super::{print_console_info, BuildManager, PrettierOptions};
+use crate::{CrateConfig, Result};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The checked out HEAD and the commit the current ref points to
+type GitState = Option<(String, Option<String>)>;
+
+/// Polls the git HEAD of the crate and rebuilds whenever the checked out ref changes.
+///
+/// Unlike the file watchers this ignores the working tree entirely, so it is suited for
+/// reviewing branches rather than editing them.
+pub fn setup_git_watcher(
+    config: &CrateConfig,
+    port: u16,
+    watcher_ip: String,
+    build_manager: Arc<BuildManager>,
+) -> Result<()> {
+    let git_dir = find_git_dir(&config.crate_dir)
+        .ok_or("preview mode requires the crate to be inside a git repository")?;
+
+    log::info!("👀 Watching git HEAD at {}", git_dir.display());
+
+    let config = config.clone();
+    let mut last_state = read_git_state(&git_dir);
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(POLL_INTERVAL);
+        // Picked up once the watcher is resumed
+        if build_manager.is_paused() {
+            continue;
+        }
+
+        let state = read_git_state(&git_dir);
+        if state == last_state {
+            continue;
+        }
+        last_state = state;
+
+        match build_manager.rebuild() {
+            Ok(res) => {
+                print_console_info(
+                    &watcher_ip,
+                    port,
+                    &config,
+                    PrettierOptions {
+                        changed: vec![git_dir.join("HEAD")],
+                        warnings: res.warnings,
+                        elapsed_time: res.elapsed_time,
+                        phases: res.phases,
+                    },
+                );
+                super::bell::ring(&config);
+            }
+            Err(err) => log::error!("{}", err),
+        }
+    });
+
+    Ok(())
+}
+
+/// Find the git directory of the repository containing `dir`, following `.git` files
+/// used by worktrees and submodules.
+fn find_git_dir(dir: &Path) -> Option<PathBuf> {
+    for ancestor in dir.ancestors() {
+        let dot_git = ancestor.join(".git");
+        if dot_git.is_dir() {
+            return Some(dot_git);
+        }
+        if dot_git.is_file() {
+            let contents = std::fs::read_to_string(&dot_git).ok()?;
+            let git_dir = contents.strip_prefix("gitdir:")?.trim();
+            return Some(ancestor.join(git_dir));
+        }
+    }
+    None
+}
+
+/// The directory with the refs of the repository. Worktrees keep their own HEAD but share the
+/// branches of the main git directory, which their `commondir` file points to.
+fn common_dir(git_dir: &Path) -> PathBuf {
+    match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common_dir) => git_dir.join(common_dir.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+fn read_git_state(git_dir: &Path) -> GitState {
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let common_dir = common_dir(git_dir);
+    let current_ref = head.strip_prefix("ref:").map(str::trim).and_then(|name| {
+        std::fs::read_to_string(common_dir.join(name))
+            .ok()
+            .or_else(|| read_packed_ref(&common_dir, name))
+    });
+    Some((head, current_ref))
+}
+
+/// Refs that haven't been touched in a while only live in the `packed-refs` file
+fn read_packed_ref(git_dir: &Path, name: &str) -> Option<String> {
+    let packed = std::fs::read_to_string(git_dir.join("packed-refs")).ok()?;
+    packed.lines().find_map(|line| {
+        let (sha, ref_name) = line.split_once(' ')?;
+        (ref_name == name).then(|| sha.to_string())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn worktrees_read_branches_from_the_common_dir() {
+        let repo = tempfile::tempdir().unwrap();
+        let common = repo.path().join(".git");
+        let worktree_git = common.join("worktrees").join("review");
+        std::fs::create_dir_all(common.join("refs/heads")).unwrap();
+        std::fs::create_dir_all(&worktree_git).unwrap();
+        std::fs::write(worktree_git.join("HEAD"), "ref: refs/heads/review\n").unwrap();
+        std::fs::write(worktree_git.join("commondir"), "../..\n").unwrap();
+
+        let checkout = repo.path().join("review");
+        std::fs::create_dir_all(&checkout).unwrap();
+        std::fs::write(
+            checkout.join(".git"),
+            format!("gitdir: {}\n", worktree_git.display()),
+        )
+        .unwrap();
+        let git_dir = find_git_dir(&checkout).unwrap();
+
+        std::fs::write(common.join("refs/heads/review"), "aaaa\n").unwrap();
+        let before = read_git_state(&git_dir);
+        assert_eq!(before.as_ref().unwrap().1.as_deref(), Some("aaaa\n"));
+
+        std::fs::write(common.join("refs/heads/review"), "bbbb\n").unwrap();
+        assert_ne!(read_git_state(&git_dir), before);
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::CrateConfig;
+
+/// How long to wait for the event of the sentinel file before warning
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Checks the watcher delivers events at all, which it silently doesn't on some network mounts
+/// and shared folders of virtual machines, by touching a sentinel file in a watched directory
+pub struct WatchProbe {
+    /// The sentinel file, unset if the check is turned off or no watched directory exists
+    sentinel: Option<PathBuf>,
+    received: AtomicBool,
+}
+
+impl WatchProbe {
+    pub fn new(config: &CrateConfig, paths: &[PathBuf]) -> Arc<Self> {
+        let enabled = config.dioxus_config.web.watcher.self_test.unwrap_or(true);
+        let sentinel = paths
+            .iter()
+            .find(|path| path.is_dir())
+            .filter(|_| enabled)
+            .map(|dir| dir.join(format!(".dioxus-watch-probe-{}", std::process::id())));
+        Arc::new(Self {
+            sentinel,
+            received: AtomicBool::new(false),
+        })
+    }
+
+    /// Note the events of the sentinel file.
+    ///
+    /// Returns true if the event was only about the sentinel and needs no further handling.
+    pub fn observe(&self, paths: &[PathBuf]) -> bool {
+        let Some(sentinel) = &self.sentinel else {
+            return false;
+        };
+        let is_sentinel = |path: &PathBuf| path.file_name() == sentinel.file_name();
+        if !paths.is_empty() && paths.iter().all(is_sentinel) {
+            self.received.store(true, Ordering::SeqCst);
+            return true;
+        }
+        false
+    }
+
+    /// Touch the sentinel file in the background and warn if its event doesn't arrive in time
+    pub fn run(self: Arc<Self>) {
+        let Some(sentinel) = self.sentinel.clone() else {
+            return;
+        };
+        std::thread::spawn(move || {
+            if let Err(err) = std::fs::write(&sentinel, "") {
+                log::debug!(
+                    "Skipping the watcher check, {}: {}",
+                    sentinel.display(),
+                    err
+                );
+                return;
+            }
+
+            let start = Instant::now();
+            while !self.received.load(Ordering::SeqCst) && start.elapsed() < PROBE_TIMEOUT {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            let _ = std::fs::remove_file(&sentinel);
+
+            if !self.received.load(Ordering::SeqCst) {
+                log::warn!(
+                    "The file watcher got no event for a test file in {} within {} seconds, so changes probably won't trigger rebuilds. This happens on some network mounts and shared folders of virtual machines, move the project to a local disk if it does. Set watcher.self_test = false to skip this check",
+                    sentinel.parent().unwrap_or(&sentinel).display(),
+                    PROBE_TIMEOUT.as_secs()
+                );
+            }
+        });
+    }
+}
@@ -1,17 +1,42 @@
-use crate::{Result, WebProxyConfig};
+use std::{net::SocketAddr, process::Command, sync::Arc, time::Instant};
+
+use super::inject;
+use crate::{CrateConfig, Result, WebProxyConfig};
 
 use anyhow::Context;
-use axum::{http::StatusCode, routing::any, Router};
+use axum::{
+    extract::ConnectInfo,
+    http::{
+        header::{HeaderName, ACCEPT_ENCODING, HOST},
+        HeaderValue, StatusCode,
+    },
+    response::IntoResponse,
+    routing::{any, MethodRouter},
+    Router,
+};
 use hyper::{Request, Response, Uri};
+use tokio::sync::Semaphore;
+use tower::limit::GlobalConcurrencyLimitLayer;
 
 #[derive(Debug, Clone)]
 struct ProxyClient {
     inner: hyper::Client<hyper_rustls::HttpsConnector<hyper::client::HttpConnector>>,
     url: Uri,
+    /// Log the upstream latency of every request
+    log: bool,
+    /// The reload client injected into html responses, if enabled
+    reload_client: Option<Arc<str>>,
+    /// The scheme of the dev server sent in `X-Forwarded-Proto`, if forwarding headers
+    forwarded_proto: Option<&'static str>,
 }
 
 impl ProxyClient {
-    fn new(url: Uri) -> Self {
+    fn new(
+        url: Uri,
+        log: bool,
+        reload_client: Option<Arc<str>>,
+        forwarded_proto: Option<&'static str>,
+    ) -> Self {
         let https = hyper_rustls::HttpsConnectorBuilder::new()
             .with_native_roots()
             .https_or_http()
@@ -20,6 +45,29 @@ impl ProxyClient {
         Self {
             inner: hyper::Client::builder().build(https),
             url,
+            log,
+            reload_client,
+            forwarded_proto,
+        }
+    }
+
+    /// Forward a request and relay the response, with the reload client if enabled
+    async fn forward(&self, mut req: Request<hyper::body::Body>) -> axum::response::Response {
+        if self.reload_client.is_some() {
+            // Ask for an uncompressed page, the client can't be injected into a compressed one
+            req.headers_mut().remove(ACCEPT_ENCODING);
+        }
+        if let Some(proto) = self.forwarded_proto {
+            add_forwarded_headers(&mut req, proto);
+        }
+        match self.send(req).await {
+            Ok(response) => match &self.reload_client {
+                Some(client) if inject::is_html(&response) => {
+                    inject::inject_reload_client(response, client)
+                }
+                _ => response.into_response(),
+            },
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
         }
     }
 
@@ -31,13 +79,69 @@ impl ProxyClient {
         uri_parts.authority = self.url.authority().cloned();
         uri_parts.scheme = self.url.scheme().cloned();
         *req.uri_mut() = Uri::from_parts(uri_parts).context("Invalid URI parts")?;
-        self.inner
-            .request(req)
-            .await
-            .map_err(crate::error::Error::ProxyRequestError)
+        if !self.log {
+            return self
+                .inner
+                .request(req)
+                .await
+                .map_err(crate::error::Error::ProxyRequestError);
+        }
+
+        let method = req.method().clone();
+        let path = req.uri().path().to_string();
+        let host = self.url.authority().map_or("", |host| host.as_str());
+        let start = Instant::now();
+        let response = self.inner.request(req).await;
+        let millis = start.elapsed().as_secs_f64() * 1000.0;
+        match &response {
+            Ok(response) => log::info!(
+                "↪️  {} {} {} from {} in {:.1}ms",
+                method,
+                path,
+                response.status().as_u16(),
+                host,
+                millis
+            ),
+            Err(err) => log::warn!(
+                "↪️  {} {} failed from {} after {:.1}ms: {}",
+                method,
+                path,
+                host,
+                millis,
+                err
+            ),
+        }
+        response.map_err(crate::error::Error::ProxyRequestError)
     }
 }
 
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+const X_FORWARDED_PROTO: HeaderName = HeaderName::from_static("x-forwarded-proto");
+const X_FORWARDED_HOST: HeaderName = HeaderName::from_static("x-forwarded-host");
+
+/// Tell the backend about the client the way a reverse proxy in production does, appending
+/// its address to the `X-Forwarded-For` it may already have
+fn add_forwarded_headers(req: &mut Request<hyper::body::Body>, proto: &'static str) {
+    let client = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    let headers = req.headers_mut();
+    if let Some(client) = client {
+        let forwarded_for = match headers.get(X_FORWARDED_FOR).and_then(|v| v.to_str().ok()) {
+            Some(forwarded_for) => format!("{}, {}", forwarded_for, client),
+            None => client.to_string(),
+        };
+        if let Ok(forwarded_for) = HeaderValue::from_str(&forwarded_for) {
+            headers.insert(X_FORWARDED_FOR, forwarded_for);
+        }
+    }
+    if let Some(host) = headers.get(HOST).cloned() {
+        headers.insert(X_FORWARDED_HOST, host);
+    }
+    headers.insert(X_FORWARDED_PROTO, HeaderValue::from_static(proto));
+}
+
 /// Add routes to the router handling the specified proxy config.
 ///
 /// We will proxy requests directed at either:
@@ -45,10 +149,37 @@ impl ProxyClient {
 /// - the exact path of the proxy config's backend URL, e.g. /api
 /// - the exact path with a trailing slash, e.g. /api/
 /// - any subpath of the backend URL, e.g. /api/foo/bar
-pub fn add_proxy(mut router: Router, proxy: &WebProxyConfig) -> Result<Router> {
-    let url: Uri = proxy.backend.parse()?;
+///
+/// With `inject_reload` the `reload_client` is injected into the html responses, and with
+/// `forward_headers` the requests say they were made over `scheme`.
+pub fn add_proxy(
+    mut router: Router,
+    proxy: &WebProxyConfig,
+    reload_client: &str,
+    scheme: &'static str,
+) -> Result<Router> {
+    let url = backend_uri(proxy)?;
+    check_concurrency(proxy)?;
     let path = url.path().to_string();
-    let client = ProxyClient::new(url);
+    let reload_client = proxy
+        .inject_reload
+        .unwrap_or(false)
+        .then(|| Arc::from(reload_client));
+    let forwarded_proto = proxy.forward_headers.unwrap_or(false).then_some(scheme);
+    let client = ProxyClient::new(
+        url,
+        proxy.log.unwrap_or(false),
+        reload_client,
+        forwarded_proto,
+    );
+    // Both routes share the limit, so it covers every request to the backend
+    let limit = proxy
+        .concurrency
+        .map(|concurrency| Arc::new(Semaphore::new(concurrency)));
+    let limited = |route: MethodRouter| match &limit {
+        Some(limit) => route.layer(GlobalConcurrencyLimitLayer::with_semaphore(limit.clone())),
+        None => route,
+    };
 
     // We also match everything after the path using a wildcard matcher.
     let wildcard_client = client.clone();
@@ -57,12 +188,7 @@ pub fn add_proxy(mut router: Router, proxy: &WebProxyConfig) -> Result<Router> {
         // Always remove trailing /'s so that the exact route
         // matches.
         path.trim_end_matches('/'),
-        any(move |req| async move {
-            client
-                .send(req)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
-        }),
+        limited(any(move |req| async move { client.forward(req).await })),
     );
 
     // Wildcard match anything else _after_ the backend URL's path.
@@ -71,21 +197,83 @@ pub fn add_proxy(mut router: Router, proxy: &WebProxyConfig) -> Result<Router> {
     let wildcard = format!("{}/*proxywildcard", path.trim_end_matches('/'));
     router = router.route(
         &wildcard,
-        any(move |req| async move {
-            wildcard_client
-                .send(req)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
-        }),
+        limited(any(
+            move |req| async move { wildcard_client.forward(req).await },
+        )),
     );
     Ok(router)
 }
 
+/// Run the `discover` commands of the proxies and use what they print as their backend URLs
+pub fn discover_backends(config: &mut CrateConfig) -> Result<()> {
+    let crate_dir = config.crate_dir.clone();
+    for proxy in config.dioxus_config.web.proxy.iter_mut().flatten() {
+        let Some(discover) = &proxy.discover else {
+            continue;
+        };
+        let output = if cfg!(windows) {
+            Command::new("cmd")
+                .args(["/C", discover])
+                .current_dir(&crate_dir)
+                .output()
+        } else {
+            Command::new("sh")
+                .args(["-c", discover])
+                .current_dir(&crate_dir)
+                .output()
+        };
+        let failed = |reason: String| {
+            crate::error::Error::CustomError(format!(
+                "Failed to discover the proxy backend with `{}`: {}",
+                discover, reason
+            ))
+        };
+        let output = output.map_err(|err| failed(err.to_string()))?;
+        if !output.status.success() {
+            return Err(failed(format!(
+                "{}\n{}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        proxy.backend = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        backend_uri(proxy).map_err(|err| failed(err.to_string()))?;
+        log::info!("🔎 Proxying to {} found by `{}`", proxy.backend, discover);
+    }
+    Ok(())
+}
+
+/// Parse the backend URL of a proxy config, which has to be absolute to be forwarded to
+pub fn backend_uri(proxy: &WebProxyConfig) -> Result<Uri> {
+    let url: Uri = proxy.backend.parse()?;
+    if url.scheme().is_none() || url.authority().is_none() {
+        return Err(crate::error::Error::CustomError(format!(
+            "Invalid proxy URL: {} has no scheme or host, use e.g. http://localhost:8000/api",
+            proxy.backend
+        )));
+    }
+    Ok(url)
+}
+
+/// A limit of 0 would keep every request to the backend waiting forever
+pub fn check_concurrency(proxy: &WebProxyConfig) -> Result<()> {
+    if proxy.concurrency == Some(0) {
+        return Err(crate::error::Error::CustomError(format!(
+            "The concurrency of the proxy to {} must be at least 1, leave it out for no limit",
+            proxy.backend
+        )));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
 
     use super::*;
 
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
     use axum::{extract::Path, Router};
 
     fn setup_servers(
@@ -99,16 +287,10 @@ mod test {
             "/*path",
             any(|path: Path<String>| async move { format!("backend: {}", path.0) }),
         );
-        let backend_server = axum::Server::bind(&"127.0.0.1:0".parse().unwrap())
-            .serve(backend_router.into_make_service());
-        let backend_addr = backend_server.local_addr();
-        let backend_handle = tokio::spawn(async move { backend_server.await.unwrap() });
+        let (backend_addr, backend_handle) = crate::server::spawn_router(backend_router);
         config.backend = format!("http://{}{}", backend_addr, config.backend);
-        let router = super::add_proxy(Router::new(), &config);
-        let server = axum::Server::bind(&"127.0.0.1:0".parse().unwrap())
-            .serve(router.unwrap().into_make_service());
-        let server_addr = server.local_addr();
-        let server_handle = tokio::spawn(async move { server.await.unwrap() });
+        let router = super::add_proxy(Router::new(), &config, "", "http");
+        let (server_addr, server_handle) = crate::server::spawn_router(router.unwrap());
         (backend_handle, server_handle, server_addr.to_string())
     }
 
@@ -120,6 +302,7 @@ mod test {
             // path together.
             // So in day to day usage, use `http://localhost:8000/api` instead!
             backend: path,
+            ..Default::default()
         };
         let (backend_handle, server_handle, server_addr) = setup_servers(config);
         let resp = hyper::Client::new()
@@ -168,4 +351,136 @@ mod test {
     async fn add_proxy_trailing_slash() {
         test_proxy_requests("/api/".to_string()).await;
     }
+
+    #[tokio::test]
+    async fn limited_proxy_forwards_every_request() {
+        let config = WebProxyConfig {
+            backend: "/api".to_string(),
+            concurrency: Some(2),
+            ..Default::default()
+        };
+
+        // The backend keeps track of how many requests it handled at once
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let backend_router = {
+            let (in_flight, max_in_flight) = (in_flight.clone(), max_in_flight.clone());
+            Router::new().route(
+                "/api/*path",
+                any(move || async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    "backend"
+                }),
+            )
+        };
+        let (backend_addr, backend_handle) = crate::server::spawn_router(backend_router);
+        let config = WebProxyConfig {
+            backend: format!("http://{}/api", backend_addr),
+            ..config
+        };
+        let router = super::add_proxy(Router::new(), &config, "", "http").unwrap();
+        let (server_addr, server_handle) = crate::server::spawn_router(router);
+
+        let requests = (0..6).map(|i| {
+            hyper::Client::new().get(format!("http://{}/api/{}", server_addr, i).parse().unwrap())
+        });
+        for resp in futures::future::join_all(requests).await {
+            assert_eq!(resp.unwrap().status(), StatusCode::OK);
+        }
+        let max = max_in_flight.load(Ordering::SeqCst);
+        assert!(
+            (1..=2).contains(&max),
+            "{} requests reached the backend at once",
+            max
+        );
+        backend_handle.abort();
+        server_handle.abort();
+    }
+
+    #[tokio::test]
+    async fn reload_client_is_injected_into_backend_pages() {
+        let backend_router = Router::new().route(
+            "/ssr/*path",
+            any(|| async { axum::response::Html("<html><body>rendered</body></html>") }),
+        );
+        let (backend_addr, backend_handle) = crate::server::spawn_router(backend_router);
+        let config = WebProxyConfig {
+            backend: format!("http://{}/ssr", backend_addr),
+            inject_reload: Some(true),
+            ..Default::default()
+        };
+        let router =
+            super::add_proxy(Router::new(), &config, "<script>reload</script>", "http").unwrap();
+        let (server_addr, server_handle) = crate::server::spawn_router(router);
+
+        let resp = hyper::Client::new()
+            .get(format!("http://{}/ssr/page", server_addr).parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            hyper::body::to_bytes(resp.into_body()).await.unwrap(),
+            "<html><body>rendered<script>reload</script></body></html>"
+        );
+        backend_handle.abort();
+        server_handle.abort();
+    }
+
+    #[tokio::test]
+    async fn client_address_is_forwarded() {
+        let backend_router = Router::new().route(
+            "/api/*path",
+            any(|headers: axum::http::HeaderMap| async move {
+                let header = |name: &str| headers[name].to_str().unwrap().to_string();
+                format!(
+                    "{} {} {}",
+                    header("x-forwarded-for"),
+                    header("x-forwarded-proto"),
+                    header("x-forwarded-host")
+                )
+            }),
+        );
+        let (backend_addr, backend_handle) = crate::server::spawn_router(backend_router);
+        let config = WebProxyConfig {
+            backend: format!("http://{}/api", backend_addr),
+            forward_headers: Some(true),
+            ..Default::default()
+        };
+        let router = super::add_proxy(Router::new(), &config, "", "http").unwrap();
+        let (server_addr, server_handle) = crate::server::spawn_router(router);
+
+        let req = Request::get(format!("http://{}/api/users", server_addr))
+            .header("x-forwarded-for", "10.0.0.1")
+            .body(hyper::Body::empty())
+            .unwrap();
+        let resp = hyper::Client::new().request(req).await.unwrap();
+        assert_eq!(
+            hyper::body::to_bytes(resp.into_body()).await.unwrap(),
+            format!("10.0.0.1, 127.0.0.1 http {}", server_addr)
+        );
+        backend_handle.abort();
+        server_handle.abort();
+    }
+
+    #[test]
+    fn zero_concurrency_is_rejected() {
+        let config = WebProxyConfig {
+            backend: "http://localhost:8000/api".to_string(),
+            concurrency: Some(0),
+            ..Default::default()
+        };
+        assert!(super::add_proxy(Router::new(), &config, "", "http").is_err());
+    }
+
+    #[test]
+    fn relative_backend_is_rejected() {
+        let config = WebProxyConfig {
+            backend: "/api".to_string(),
+            ..Default::default()
+        };
+        assert!(backend_uri(&config).is_err());
+        assert!(super::add_proxy(Router::new(), &config, "", "http").is_err());
+    }
 }
@@ -0,0 +1,160 @@
+use std::{collections::HashMap, io, sync::Arc};
+
+use rustls::{
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+
+use super::client_auth::{client_verifier, invalid_data, read_certs, read_key};
+use crate::WebHttpsCertificate;
+
+/// Picks the certificate of the host a client asks for in its TLS handshake
+struct SniResolver {
+    /// By lowercase host name
+    hosts: HashMap<String, Arc<CertifiedKey>>,
+    /// For clients asking for another host or none, e.g. by IP address
+    default: Option<Arc<CertifiedKey>>,
+}
+
+impl SniResolver {
+    fn new(
+        certificates: &[WebHttpsCertificate],
+        default: Option<(String, String)>,
+    ) -> io::Result<Self> {
+        let mut hosts = HashMap::new();
+        for certificate in certificates {
+            let key = certified_key(&certificate.cert_path, &certificate.key_path)?;
+            if hosts
+                .insert(certificate.host.to_ascii_lowercase(), key)
+                .is_some()
+            {
+                return Err(invalid_data(format!(
+                    "{} has more than one certificate",
+                    certificate.host
+                )));
+            }
+        }
+        let default = match default {
+            Some((cert_path, key_path)) => Some(certified_key(&cert_path, &key_path)?),
+            None => None,
+        };
+        Ok(Self { hosts, default })
+    }
+
+    fn certificate_for(&self, server_name: Option<&str>) -> Option<Arc<CertifiedKey>> {
+        server_name
+            .and_then(|name| self.hosts.get(&name.to_ascii_lowercase()))
+            .or(self.default.as_ref())
+            .cloned()
+    }
+}
+
+impl ResolvesServerCert for SniResolver {
+    fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        self.certificate_for(client_hello.server_name())
+    }
+}
+
+/// A TLS config serving each of the `web.https.certificates` to its host, and the `default`
+/// certificate and key paths to everything else.
+///
+/// Clients need a certificate signed by `client_ca` if it's set, like with
+/// [`client_auth_config`](super::client_auth::client_auth_config).
+pub fn sni_config(
+    certificates: &[WebHttpsCertificate],
+    default: Option<(String, String)>,
+    client_ca: Option<&str>,
+) -> io::Result<rustls::ServerConfig> {
+    let resolver = SniResolver::new(certificates, default)?;
+
+    let builder = rustls::ServerConfig::builder().with_safe_defaults();
+    let builder = match client_ca {
+        Some(client_ca) => builder.with_client_cert_verifier(client_verifier(client_ca)?),
+        None => builder.with_no_client_auth(),
+    };
+    let mut config = builder.with_cert_resolver(Arc::new(resolver));
+    // The protocols `RustlsConfig::from_pem_file` offers
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+fn certified_key(cert_path: &str, key_path: &str) -> io::Result<Arc<CertifiedKey>> {
+    let certs = read_certs(cert_path)?;
+    if certs.is_empty() {
+        return Err(invalid_data(format!("{} has no certificates", cert_path)));
+    }
+    let key = rustls::sign::any_supported_type(&read_key(key_path)?)
+        .map_err(|err| invalid_data(format!("{}: {}", key_path, err)))?;
+    Ok(Arc::new(CertifiedKey::new(certs, key)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::path::Path;
+
+    /// Write a self-signed certificate for `host` and its key, returning their paths
+    fn self_signed(dir: &Path, host: &str) -> (String, String) {
+        let cert = rcgen::generate_simple_self_signed(vec![host.to_string()]).unwrap();
+        let cert_path = dir.join(format!("{}.pem", host));
+        let key_path = dir.join(format!("{}-key.pem", host));
+        std::fs::write(&cert_path, cert.serialize_pem().unwrap()).unwrap();
+        std::fs::write(&key_path, cert.serialize_private_key_pem()).unwrap();
+        (
+            cert_path.display().to_string(),
+            key_path.display().to_string(),
+        )
+    }
+
+    fn certificate(host: &str, (cert_path, key_path): &(String, String)) -> WebHttpsCertificate {
+        WebHttpsCertificate {
+            host: host.to_string(),
+            cert_path: cert_path.clone(),
+            key_path: key_path.clone(),
+        }
+    }
+
+    #[test]
+    fn certificates_are_picked_by_server_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = self_signed(dir.path(), "app.local");
+        let api = self_signed(dir.path(), "api.local");
+        let fallback = self_signed(dir.path(), "localhost");
+        let resolver = SniResolver::new(
+            &[
+                certificate("app.local", &app),
+                certificate("API.local", &api),
+            ],
+            Some(fallback.clone()),
+        )
+        .unwrap();
+
+        let served_cert =
+            |server_name| resolver.certificate_for(server_name).unwrap().cert[0].clone();
+        let cert_of = |(cert_path, _): &(String, String)| read_certs(cert_path).unwrap()[0].clone();
+        assert_eq!(served_cert(Some("app.local")), cert_of(&app));
+        assert_eq!(served_cert(Some("api.local")), cert_of(&api));
+        assert_eq!(served_cert(Some("other.local")), cert_of(&fallback));
+        assert_eq!(served_cert(None), cert_of(&fallback));
+
+        // Without a default only the listed hosts get a certificate
+        let resolver = SniResolver::new(&[certificate("app.local", &app)], None).unwrap();
+        assert!(resolver.certificate_for(None).is_none());
+
+        assert!(sni_config(&[certificate("app.local", &app)], Some(fallback), None).is_ok());
+    }
+
+    #[test]
+    fn duplicate_hosts_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = self_signed(dir.path(), "app.local");
+        let other = self_signed(dir.path(), "other.local");
+        let duplicates = [
+            certificate("app.local", &app),
+            certificate("App.Local", &other),
+        ];
+        assert!(SniResolver::new(&duplicates, None).is_err());
+        assert!(sni_config(&duplicates, None, None).is_err());
+    }
+}
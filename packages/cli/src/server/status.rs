@@ -0,0 +1,50 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{extract::Extension, Json};
+use serde::Serialize;
+
+/// The state of the most recent build, served at `/_dioxus/status`
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildStatus {
+    pub state: BuildState,
+    /// Why the most recent build failed, cleared by the next successful build
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildState {
+    Building,
+    Ok,
+    Failed,
+}
+
+pub type SharedBuildStatus = Arc<Mutex<BuildStatus>>;
+
+impl BuildStatus {
+    pub fn new(state: BuildState) -> Self {
+        Self { state, error: None }
+    }
+
+    /// A build started, the error of the last one is kept until it finishes
+    pub fn start(&mut self) {
+        self.state = BuildState::Building;
+    }
+
+    pub fn finish<T>(&mut self, result: &crate::Result<T>) {
+        match result {
+            Ok(_) => {
+                self.state = BuildState::Ok;
+                self.error = None;
+            }
+            Err(err) => {
+                self.state = BuildState::Failed;
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+}
+
+pub async fn status_handler(Extension(status): Extension<SharedBuildStatus>) -> Json<BuildStatus> {
+    Json(status.lock().unwrap().clone())
+}
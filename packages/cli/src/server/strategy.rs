@@ -0,0 +1,256 @@
+use std::path::{Component, Path, PathBuf};
+
+use globset::{Glob, GlobMatcher};
+
+use crate::{CrateConfig, ReloadStrategy, WatchGroup, WatcherStrategyRule};
+
+/// The decision table the hot reload watcher uses to pick what to do with a changed path.
+///
+/// The user's `watcher.strategy` rules are evaluated first, in order, then the paths of the
+/// `watcher.groups`, followed by the default rules: rust files are hot reloaded and anything
+/// else triggers a full rebuild.
+pub struct StrategyMatrix {
+    root: PathBuf,
+    rules: Vec<(GlobMatcher, ReloadStrategy)>,
+    /// The absolute paths of the groups with `affects_frontend = false`
+    backend: Vec<PathBuf>,
+}
+
+impl StrategyMatrix {
+    pub fn new(root: PathBuf, rules: &[WatcherStrategyRule]) -> Self {
+        let defaults = [(
+            Glob::new("**/*.rs").unwrap().compile_matcher(),
+            ReloadStrategy::RsxHotreload,
+        )];
+
+        let rules = rules
+            .iter()
+            .filter_map(|rule| match Glob::new(&rule.pattern) {
+                Ok(glob) => Some((glob.compile_matcher(), rule.action)),
+                Err(err) => {
+                    log::warn!("Ignoring invalid watcher strategy pattern: {}", err);
+                    None
+                }
+            })
+            .chain(defaults)
+            .collect();
+
+        Self {
+            root,
+            rules,
+            backend: Vec::new(),
+        }
+    }
+
+    pub fn from_config(config: &CrateConfig) -> Self {
+        let watcher = &config.dioxus_config.web.watcher;
+        let rules: Vec<_> = watcher
+            .strategy
+            .iter()
+            .flatten()
+            .cloned()
+            .chain(group_rules(watcher.groups.iter().flatten()))
+            .collect();
+        let mut matrix = Self::new(config.crate_dir.clone(), &rules);
+        matrix.backend = backend_paths(&config.crate_dir, watcher.groups.iter().flatten());
+        matrix
+    }
+
+    /// Find the strategy of the first rule matching the path, relative to the crate directory
+    pub fn strategy_for(&self, path: &Path) -> ReloadStrategy {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        self.rules
+            .iter()
+            .find(|(matcher, _)| matcher.is_match(relative))
+            .map(|(_, strategy)| *strategy)
+            .unwrap_or(ReloadStrategy::Rebuild)
+    }
+
+    /// Whether a rebuild for the changed paths should reload the pages, false only when every
+    /// path is inside a group with `affects_frontend = false`
+    pub fn affects_frontend(&self, paths: &[PathBuf]) -> bool {
+        paths.is_empty()
+            || paths.iter().any(|path| {
+                let path = normalize(&self.root.join(path));
+                !self.backend.iter().any(|backend| path.starts_with(backend))
+            })
+    }
+}
+
+/// Rules matching each path of the groups and everything inside it
+fn group_rules<'a>(
+    groups: impl Iterator<Item = &'a WatchGroup> + 'a,
+) -> impl Iterator<Item = WatcherStrategyRule> + 'a {
+    groups.flat_map(|group| {
+        group.paths.iter().flat_map(move |path| {
+            let path = path.to_string_lossy().replace('\\', "/");
+            let path = path.trim_end_matches('/');
+            [path.to_string(), format!("{}/**", path)].map(|pattern| WatcherStrategyRule {
+                pattern,
+                action: group.action,
+            })
+        })
+    })
+}
+
+/// The paths of the groups that don't affect the frontend, resolved against the crate
+/// directory.
+///
+/// The events of a group behind a symlink come with the real path if `follow_symlinks` is
+/// set, so that path is kept as well.
+fn backend_paths<'a>(root: &Path, groups: impl Iterator<Item = &'a WatchGroup>) -> Vec<PathBuf> {
+    groups
+        .filter(|group| group.affects_frontend == Some(false))
+        .flat_map(|group| &group.paths)
+        .flat_map(|path| {
+            let path = normalize(&root.join(path));
+            let real = path.canonicalize().ok().filter(|real| *real != path);
+            [Some(path), real]
+        })
+        .flatten()
+        .collect()
+}
+
+/// Remove the `.` and `..` components of a path without touching the file system, like the
+/// paths notify reports
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule(pattern: &str, action: ReloadStrategy) -> WatcherStrategyRule {
+        WatcherStrategyRule {
+            pattern: pattern.to_string(),
+            action,
+        }
+    }
+
+    #[test]
+    fn default_matrix() {
+        let matrix = StrategyMatrix::new(PathBuf::from("/app"), &[]);
+        assert_eq!(
+            matrix.strategy_for(Path::new("/app/src/main.rs")),
+            ReloadStrategy::RsxHotreload
+        );
+        assert_eq!(
+            matrix.strategy_for(Path::new("/app/public/style.css")),
+            ReloadStrategy::Rebuild
+        );
+        assert_eq!(
+            matrix.strategy_for(Path::new("/app/Cargo.toml")),
+            ReloadStrategy::Rebuild
+        );
+    }
+
+    #[test]
+    fn first_match_wins() {
+        let matrix = StrategyMatrix::new(
+            PathBuf::from("/app"),
+            &[
+                rule("src/generated/**", ReloadStrategy::Ignore),
+                rule("**/*.css", ReloadStrategy::CssInject),
+                rule("public/**", ReloadStrategy::AssetCopy),
+            ],
+        );
+        assert_eq!(
+            matrix.strategy_for(Path::new("/app/src/generated/bindings.rs")),
+            ReloadStrategy::Ignore
+        );
+        assert_eq!(
+            matrix.strategy_for(Path::new("/app/src/main.rs")),
+            ReloadStrategy::RsxHotreload
+        );
+        assert_eq!(
+            matrix.strategy_for(Path::new("/app/public/style.css")),
+            ReloadStrategy::CssInject
+        );
+        assert_eq!(
+            matrix.strategy_for(Path::new("/app/public/logo.png")),
+            ReloadStrategy::AssetCopy
+        );
+    }
+
+    #[test]
+    fn invalid_patterns_are_skipped() {
+        let matrix = StrategyMatrix::new(
+            PathBuf::from("/app"),
+            &[rule("src/[", ReloadStrategy::Ignore)],
+        );
+        assert_eq!(
+            matrix.strategy_for(Path::new("/app/src/main.rs")),
+            ReloadStrategy::RsxHotreload
+        );
+    }
+
+    #[test]
+    fn groups_apply_to_their_paths() {
+        let groups = [
+            WatchGroup {
+                paths: vec![PathBuf::from("content/")],
+                action: ReloadStrategy::ReloadOnly,
+                affects_frontend: None,
+            },
+            WatchGroup {
+                paths: vec![PathBuf::from("data.json")],
+                action: ReloadStrategy::Ignore,
+                affects_frontend: None,
+            },
+        ];
+        let rules: Vec<_> = [rule("content/drafts/**", ReloadStrategy::Ignore)]
+            .into_iter()
+            .chain(group_rules(groups.iter()))
+            .collect();
+        let matrix = StrategyMatrix::new(PathBuf::from("/app"), &rules);
+        assert_eq!(
+            matrix.strategy_for(Path::new("/app/content/posts/hello.md")),
+            ReloadStrategy::ReloadOnly
+        );
+        assert_eq!(
+            matrix.strategy_for(Path::new("/app/content/drafts/wip.md")),
+            ReloadStrategy::Ignore
+        );
+        assert_eq!(
+            matrix.strategy_for(Path::new("/app/data.json")),
+            ReloadStrategy::Ignore
+        );
+        assert_eq!(
+            matrix.strategy_for(Path::new("/app/contents.md")),
+            ReloadStrategy::Rebuild
+        );
+    }
+
+    #[test]
+    fn backend_groups_do_not_reload_the_pages() {
+        let groups = [WatchGroup {
+            paths: vec![PathBuf::from("../server")],
+            action: ReloadStrategy::Rebuild,
+            affects_frontend: Some(false),
+        }];
+        let root = PathBuf::from("/app");
+        let mut matrix = StrategyMatrix::new(root.clone(), &[]);
+        matrix.backend = backend_paths(&root, groups.iter());
+        // As notify reports the paths, with the watch path normalized or not
+        assert!(!matrix.affects_frontend(&[PathBuf::from("/server/src/api.rs")]));
+        assert!(!matrix.affects_frontend(&[PathBuf::from("/app/../server/src/api.rs")]));
+        assert!(matrix.affects_frontend(&[
+            PathBuf::from("/server/src/api.rs"),
+            PathBuf::from("/app/src/main.rs"),
+        ]));
+        // Only the group directory itself, not every path sharing its name as a prefix
+        assert!(matrix.affects_frontend(&[PathBuf::from("/server-tools/run.rs")]));
+        assert!(matrix.affects_frontend(&[]));
+    }
+}
@@ -0,0 +1,105 @@
+use std::{sync::Arc, time::Instant};
+
+use axum::{
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+
+use super::proxy;
+use crate::CrateConfig;
+
+/// The path prefixes of the proxies, to tell proxied requests apart in the timing header
+#[derive(Debug, Default)]
+pub struct TimedRoutes {
+    proxy_paths: Vec<String>,
+}
+
+impl TimedRoutes {
+    pub fn from_config(config: &CrateConfig) -> Self {
+        let proxy_paths = config
+            .dioxus_config
+            .web
+            .proxy
+            .iter()
+            .flatten()
+            .filter_map(|proxy_config| proxy::backend_uri(proxy_config).ok())
+            .map(|url| url.path().trim_end_matches('/').to_string())
+            .collect();
+        Self { proxy_paths }
+    }
+
+    /// The name of the metric for a request path
+    fn metric_for(&self, path: &str) -> &'static str {
+        let proxied = self.proxy_paths.iter().any(|prefix| {
+            path == prefix
+                || path
+                    .strip_prefix(prefix.as_str())
+                    .map_or(false, |rest| rest.starts_with('/'))
+        });
+        if proxied {
+            "proxy"
+        } else {
+            "serve"
+        }
+    }
+}
+
+/// Add a `Server-Timing` header with how long the request took to handle, shown in the
+/// timing tab of the browser's network panel
+pub async fn add_server_timing<B>(
+    req: Request<B>,
+    next: Next<B>,
+    routes: Arc<TimedRoutes>,
+) -> Response {
+    let metric = routes.metric_for(req.uri().path());
+    let start = Instant::now();
+    let mut response = next.run(req).await;
+
+    let millis = start.elapsed().as_secs_f64() * 1000.0;
+    if let Ok(value) = HeaderValue::from_str(&format!("{};dur={:.2}", metric, millis)) {
+        response.headers_mut().append("server-timing", value);
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use axum::{body::Body, middleware, routing::get, Router};
+
+    #[test]
+    fn proxied_paths_are_told_apart() {
+        let routes = TimedRoutes {
+            proxy_paths: vec!["/api".to_string()],
+        };
+        assert_eq!(routes.metric_for("/api"), "proxy");
+        assert_eq!(routes.metric_for("/api/users"), "proxy");
+        assert_eq!(routes.metric_for("/apiary"), "serve");
+        assert_eq!(routes.metric_for("/index.html"), "serve");
+    }
+
+    #[tokio::test]
+    async fn responses_get_a_timing_header() {
+        let routes = Arc::new(TimedRoutes::default());
+        let router =
+            Router::new()
+                .route("/", get(|| async { "hello" }))
+                .layer(middleware::from_fn(
+                    move |req: Request<Body>, next: Next<Body>| {
+                        add_server_timing(req, next, routes.clone())
+                    },
+                ));
+        let (addr, handle) = crate::server::spawn_router(router);
+
+        let resp = hyper::Client::new()
+            .get(format!("http://{}/", addr).parse().unwrap())
+            .await
+            .unwrap();
+        handle.abort();
+        let timing = resp.headers()["server-timing"].to_str().unwrap();
+        assert!(timing.starts_with("serve;dur="), "{}", timing);
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{CrateConfig, ReloadCondition};
+
+/// Decides whether changes to the configured `reload_only_paths` should reload the browser.
+///
+/// These paths never trigger a rebuild, the page is just reloaded so it can fetch the new
+/// data.
+pub struct ReloadTrigger {
+    paths: Vec<PathBuf>,
+    condition: ReloadCondition,
+    last_seen: HashMap<PathBuf, Option<String>>,
+}
+
+impl ReloadTrigger {
+    pub fn new(config: &CrateConfig) -> Self {
+        let watcher = &config.dioxus_config.web.watcher;
+        let paths: Vec<PathBuf> = watcher
+            .reload_only_paths
+            .iter()
+            .flatten()
+            .map(|path| config.crate_dir.join(path))
+            .collect();
+        let condition = watcher.reload_only_when.unwrap_or_default();
+
+        // Remember what the files look like now so the first edit can be compared against it
+        let mut last_seen = HashMap::new();
+        if condition == ReloadCondition::ParsedChanged {
+            for path in &paths {
+                for entry in walkdir::WalkDir::new(path).into_iter().flatten() {
+                    if entry.path().is_file() {
+                        last_seen.insert(entry.path().to_path_buf(), normalized(entry.path()));
+                    }
+                }
+            }
+        }
+
+        Self {
+            paths,
+            condition,
+            last_seen,
+        }
+    }
+
+    /// The paths that need to be watched for this trigger
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Returns true if every changed path is handled by this trigger instead of a rebuild
+    pub fn handles_all(&self, changed: &[PathBuf]) -> bool {
+        !changed.is_empty()
+            && changed
+                .iter()
+                .all(|path| self.paths.iter().any(|root| path.starts_with(root)))
+    }
+
+    /// Returns true if any of the changed paths should reload the browser
+    pub fn should_reload(&mut self, changed: &[PathBuf]) -> bool {
+        match self.condition {
+            ReloadCondition::Always => true,
+            ReloadCondition::ParsedChanged => {
+                let mut reload = false;
+                for path in changed {
+                    let current = normalized(path);
+                    if self.last_seen.get(path) != Some(&current) {
+                        self.last_seen.insert(path.clone(), current);
+                        reload = true;
+                    }
+                }
+                reload
+            }
+        }
+    }
+}
+
+/// Read a file into a form where formatting-only edits compare equal.
+///
+/// JSON and TOML files are parsed and re-serialized. Any other file, or a file that doesn't
+/// parse, is compared by its raw contents.
+fn normalized(path: &Path) -> Option<String> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    let parsed = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str::<serde_json::Value>(&contents)
+            .ok()
+            .map(|value| value.to_string()),
+        Some("toml") => toml::from_str::<toml::Value>(&contents)
+            .ok()
+            .map(|value| value.to_string()),
+        _ => None,
+    };
+    Some(parsed.unwrap_or(contents))
+}
@@ -0,0 +1,60 @@
+use std::time::{Duration, Instant};
+
+use colored::Colorize;
+
+/// How many times to try connecting while the server is still binding
+const CONNECT_ATTEMPTS: usize = 10;
+
+/// Request the app once after the server starts.
+///
+/// This primes the OS file cache and surfaces misconfigurations in the console before the
+/// browser is opened.
+pub async fn warmup(url: String) {
+    // The dev certificates are usually self signed
+    let client = match reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+    {
+        Ok(client) => client,
+        Err(err) => {
+            log::warn!("Failed to create the warmup client: {}", err);
+            return;
+        }
+    };
+
+    for _ in 0..CONNECT_ATTEMPTS {
+        let start = Instant::now();
+        match client.get(&url).send().await {
+            Ok(response) => {
+                let status = response.status();
+                let elapsed = start.elapsed().as_millis();
+                if status.is_server_error() {
+                    log::error!(
+                        "{}",
+                        format!(
+                            "Warmup request to {} failed with {} after {} millis",
+                            url, status, elapsed
+                        )
+                        .red()
+                        .bold()
+                    );
+                } else {
+                    log::info!(
+                        "🔥 Warmup request to {} returned {} in {} millis",
+                        url,
+                        status,
+                        elapsed
+                    );
+                }
+                return;
+            }
+            Err(err) if err.is_connect() => tokio::time::sleep(Duration::from_millis(200)).await,
+            Err(err) => {
+                log::warn!("Warmup request to {} failed: {}", url, err);
+                return;
+            }
+        }
+    }
+
+    log::warn!("Warmup request to {} could not connect to the server", url);
+}
@@ -29,26 +29,38 @@ pub(crate) fn init() -> UnboundedReceiver<Template<'static>> {
         _ => "ws:",
     };
 
-    let url = format!(
-        "{protocol}//{}/_dioxus/hot_reload",
-        window.location().host().unwrap()
-    );
+    // `dx serve` sets this when the dev routes are served on their own loopback port
+    let origin = js_sys::Reflect::get(&window, &"__DIOXUS_CONTROL__".into())
+        .ok()
+        .and_then(|origin| origin.as_string())
+        .unwrap_or_else(|| format!("{protocol}//{}", window.location().host().unwrap()));
+    // Asks `dx serve` for deflated templates, which it sends with `web.serve.ws_compression`
+    let url = format!("{origin}/_dioxus/hot_reload?compress=deflate");
 
     let ws = WebSocket::new(&url).unwrap();
+    ws.set_binary_type(web_sys::BinaryType::Arraybuffer);
 
     let (tx, rx) = futures_channel::mpsc::unbounded();
 
     // change the rsx when new data is received
     let cl = Closure::wrap(Box::new(move |e: MessageEvent| {
-        if let Ok(text) = e.data().dyn_into::<js_sys::JsString>() {
-            let text: Result<String, _> = text.try_into();
-            if let Ok(string) = text {
-                let val = serde_json::from_str::<serde_json::Value>(&string).unwrap();
-                // leak the value
-                let val: &'static serde_json::Value = Box::leak(Box::new(val));
-                let template: Template<'_> = Template::deserialize(val).unwrap();
-                tx.unbounded_send(template).unwrap();
-            }
+        let data = e.data();
+        let text: Option<String> = if let Ok(text) = data.clone().dyn_into::<js_sys::JsString>() {
+            text.try_into().ok()
+        } else if let Ok(deflated) = data.dyn_into::<js_sys::ArrayBuffer>() {
+            let deflated = js_sys::Uint8Array::new(&deflated).to_vec();
+            miniz_oxide::inflate::decompress_to_vec(&deflated)
+                .ok()
+                .and_then(|json| String::from_utf8(json).ok())
+        } else {
+            None
+        };
+        if let Some(string) = text {
+            let val = serde_json::from_str::<serde_json::Value>(&string).unwrap();
+            // leak the value
+            let val: &'static serde_json::Value = Box::leak(Box::new(val));
+            let template: Template<'_> = Template::deserialize(val).unwrap();
+            tx.unbounded_send(template).unwrap();
         }
     }) as Box<dyn FnMut(MessageEvent)>);
 